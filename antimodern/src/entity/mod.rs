@@ -1,4 +1,9 @@
-use glam::{Quat, Vec3};
+use common::projection::Perspective;
+use glam::Mat4;
+
+pub use common::{projection::FovAxis, transform::Transform};
+
+pub mod scene_graph;
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Scene {
@@ -7,33 +12,175 @@ pub struct Scene {
     pub particle_system: ParticleSystem,
 }
 
+impl Scene {
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        match entity {
+            Entity::Camera => self.camera.parent,
+            Entity::Cube => self.cube.parent,
+            Entity::ParticleSystem => self.particle_system.parent,
+        }
+    }
+}
+
+// Names an entity of the scene, for parenting
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Entity {
+    Camera,
+    Cube,
+    ParticleSystem,
+}
+
+impl Entity {
+    pub const ALL: [Entity; 3] = [Entity::Camera, Entity::Cube, Entity::ParticleSystem];
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Camera {
+    // Relative to the parent
     pub transform: Transform,
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    pub world_matrix: Mat4,
+    // In degrees, along fov_axis
     pub fov: f32,
+    pub fov_axis: FovAxis,
     pub aspect_ratio: f32,
     pub near: f32,
     pub far: f32,
+    // Maps near to depth 1 and far to 0, which spreads float depth precision more evenly
+    pub reverse_z: bool,
+    // Ignores far and never clips distant geometry
+    pub infinite_far: bool,
+}
+
+impl Camera {
+    // The camera looks along +Z of its world matrix, scale is ignored
+    pub fn view_matrix(&self) -> Mat4 {
+        let (_, rotation, translation) = self.world_matrix.to_scale_rotation_translation();
+        Mat4::from_rotation_translation(rotation, translation).inverse()
+    }
+
+    pub fn perspective(&self) -> Perspective {
+        Perspective {
+            fov: self.fov,
+            fov_axis: self.fov_axis,
+            aspect_ratio: self.aspect_ratio,
+            near: self.near,
+            far: self.far,
+            reverse_z: self.reverse_z,
+            infinite_far: self.infinite_far,
+        }
+    }
+
+    pub fn projection_matrix(&self) -> Mat4 {
+        self.perspective().matrix()
+    }
+
+    // Depth the depth buffer is cleared to
+    pub fn far_depth(&self) -> f32 {
+        self.perspective().far_depth()
+    }
 }
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Cube {
+    // Relative to the parent
     pub transform: Transform,
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    pub world_matrix: Mat4,
 }
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct ParticleSystem {
+    // Relative to the parent
     pub transform: Transform,
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    pub world_matrix: Mat4,
     pub max_count: u32,
     pub particle_size: f32,
-    pub lifetime: u32,
+    // In seconds, zero for particles that never respawn
+    pub lifetime: f32,
+    // In units per second
     pub min_speed: f32,
     pub max_speed: f32,
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
-pub struct Transform {
-    pub position: Vec3,
-    pub rotation: Quat,
-    pub scale: Vec3,
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::{vec3, EulerRot, Quat, Vec3};
+
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera {
+            transform: Transform {
+                scale: Vec3::ONE,
+                ..Default::default()
+            },
+            parent: None,
+            world_matrix: Mat4::IDENTITY,
+            fov: 90.0,
+            fov_axis: FovAxis::Vertical,
+            aspect_ratio: 2.0,
+            near: 1.0,
+            far: 2.0,
+            reverse_z: false,
+            infinite_far: false,
+        }
+    }
+
+    #[test]
+    fn view_moves_camera_to_origin_looking_along_z() {
+        let mut camera = camera();
+        camera.transform.position = vec3(1.0, 2.0, 3.0);
+        camera.transform.rotation = Quat::from_rotation_y(FRAC_PI_2);
+        camera.world_matrix = camera.transform.matrix();
+        let view = camera.view_matrix();
+        let ahead = view.transform_point3(vec3(6.0, 2.0, 3.0));
+        assert!(ahead.abs_diff_eq(vec3(0.0, 0.0, 5.0), 1e-5), "{}", ahead);
+        let origin = view.transform_point3(vec3(1.0, 2.0, 3.0));
+        assert!(origin.abs_diff_eq(Vec3::ZERO, 1e-5), "{}", origin);
+    }
+
+    #[test]
+    fn view_matches_look_at_without_roll() {
+        let mut camera = camera();
+        camera.transform.position = vec3(-2.0, 0.5, 4.0);
+        camera.transform.rotation = Quat::from_euler(EulerRot::YXZ, 0.7, -0.4, 0.0);
+        camera.world_matrix = camera.transform.matrix();
+        let Transform {
+            position, rotation, ..
+        } = camera.transform;
+        let view = camera.view_matrix();
+        let look_at = Mat4::look_at_lh(position, position + rotation * Vec3::Z, Vec3::Y);
+        assert!(
+            view.abs_diff_eq(look_at, 1e-5),
+            "{} is not {}",
+            view,
+            look_at
+        );
+    }
+
+    #[test]
+    fn view_ignores_scale_inherited_from_parent() {
+        let mut camera = camera();
+        camera.transform.position = vec3(1.0, 2.0, 3.0);
+        camera.transform.rotation = Quat::from_rotation_y(0.3);
+        camera.world_matrix = camera.transform.matrix();
+        let expected = camera.view_matrix();
+        camera.transform.scale = Vec3::splat(4.0);
+        camera.world_matrix = camera.transform.matrix();
+        let view = camera.view_matrix();
+        assert!(
+            view.abs_diff_eq(expected, 1e-5),
+            "{} is not {}",
+            view,
+            expected
+        );
+    }
 }
@@ -1,8 +1,8 @@
-use std::{f32::consts::PI, thread::sleep, time::Duration};
+use std::{f32::consts::PI, time::Instant};
 
-use anyhow::{Context, Result};
-use glam::{vec3, EulerRot, Quat, Vec3};
-use log::{debug, info};
+use anyhow::{bail, Context, Result};
+use glam::{vec3, EulerRot, Mat4, Quat, Vec3};
+use log::{debug, error, info};
 use pollster::FutureExt;
 
 mod entity;
@@ -11,6 +11,8 @@ mod renderer;
 fn main() -> Result<()> {
     env_logger::init();
 
+    let seed = parse_seed_arg()?;
+
     let event_loop = winit::event_loop::EventLoop::new();
 
     let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
@@ -36,10 +38,15 @@ fn main() -> Result<()> {
                     rotation: Quat::IDENTITY,
                     ..Default::default()
                 },
+                parent: None,
+                world_matrix: Mat4::IDENTITY,
                 fov: 60.,
+                fov_axis: entity::FovAxis::Vertical,
                 aspect_ratio,
                 near: 0.1,
                 far: 1000.,
+                reverse_z: false,
+                infinite_far: false,
             }
         },
         cube: entity::Cube {
@@ -48,6 +55,8 @@ fn main() -> Result<()> {
                 rotation: Quat::from_axis_angle(Vec3::X, PI * -0.125),
                 scale: Vec3::ONE,
             },
+            parent: None,
+            world_matrix: Mat4::IDENTITY,
         },
         particle_system: entity::ParticleSystem {
             transform: entity::Transform {
@@ -55,14 +64,20 @@ fn main() -> Result<()> {
                 rotation: Quat::from_axis_angle(Vec3::X, PI * -0.25),
                 scale: Vec3::ONE * 1.5,
             },
+            parent: None,
+            world_matrix: Mat4::IDENTITY,
             max_count: 10000,
             particle_size: 0.01,
-            lifetime: 0,
+            lifetime: 0.,
             min_speed: 0.01,
             max_speed: 1.,
+            seed,
         },
     };
 
+    let mut entity_graph = entity::scene_graph::EntityGraph::new();
+    entity_graph.update(&mut scene);
+
     info!("{:#?}", &scene);
 
     let cube_pipeline = renderer::cube::PipelineState::new(
@@ -72,7 +87,7 @@ fn main() -> Result<()> {
         &scene,
     );
 
-    let particle_pipeline = renderer::particles::PipelineState::new(
+    let mut particle_pipeline = renderer::particles::PipelineState::new(
         renderer.device(),
         renderer.surface_format(),
         renderer.depth_texture_format(),
@@ -88,11 +103,12 @@ fn main() -> Result<()> {
 
     // std::thread::spawn(move || loop {
     //     instance.poll_all(true);
-    //     sleep(Duration::from_millis(1));
+    //     std::thread::sleep(std::time::Duration::from_millis(1));
     // });
 
     let mut current_sample = 1;
     let mut cursor_locked = false;
+    let mut last_rendered_at = Instant::now();
 
     event_loop.run(move |e, _, control_flow| {
         use winit::{
@@ -112,12 +128,16 @@ fn main() -> Result<()> {
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
                 WindowEvent::Resized(size) => {
                     renderer.resize(size);
-                    scene.camera.aspect_ratio = size.width as f32 / size.height as f32;
+                    if size.width > 0 && size.height > 0 {
+                        scene.camera.aspect_ratio = size.width as f32 / size.height as f32;
+                    }
                 }
                 WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                     renderer.resize(*new_inner_size);
-                    scene.camera.aspect_ratio =
-                        new_inner_size.width as f32 / new_inner_size.height as f32;
+                    if new_inner_size.width > 0 && new_inner_size.height > 0 {
+                        scene.camera.aspect_ratio =
+                            new_inner_size.width as f32 / new_inner_size.height as f32;
+                    }
                 }
                 WindowEvent::MouseInput {
                     state: ElementState::Released,
@@ -185,19 +205,51 @@ fn main() -> Result<()> {
                 scene.cube.transform.rotation *= Quat::from_axis_angle(Vec3::Y, PI * 0.01);
                 scene.particle_system.transform.rotation *=
                     Quat::from_axis_angle(Vec3::Y, PI * 0.001);
+                entity_graph.update(&mut scene);
 
                 // cube_pipeline.update(&scene).block_on().unwrap();
-                particle_pipeline.update(renderer.device(), &scene).unwrap();
+                let rendered_at = Instant::now();
+                let delta_time = rendered_at.duration_since(last_rendered_at).as_secs_f32();
+                last_rendered_at = rendered_at;
+
+                particle_pipeline
+                    .update(renderer.device(), &scene, delta_time)
+                    .unwrap();
                 // billboard_pipeline.update(&scene).block_on().unwrap();
 
-                match current_sample {
-                    1 => renderer.render(&particle_pipeline),
-                    2 => renderer.render(&cube_pipeline),
-                    3 => renderer.render(&billboard_pipeline),
-                    _ => (),
+                let result = match current_sample {
+                    1 => renderer.render(&particle_pipeline, &scene.camera),
+                    2 => renderer.render(&cube_pipeline, &scene.camera),
+                    3 => renderer.render(&billboard_pipeline, &scene.camera),
+                    _ => Ok(()),
                 };
+                if let Err(err) = result {
+                    error!("Failed to render: {:?}", err);
+                    *control_flow = ControlFlow::Exit;
+                }
             }
             _ => (),
         }
     });
 }
+
+fn parse_seed_arg() -> Result<Option<u64>> {
+    let mut seed = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value = args.next().context("--seed requires a number")?;
+                seed = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid seed: {}", value))?,
+                );
+            }
+            _ => bail!("Unknown argument: {}", arg),
+        }
+    }
+
+    Ok(seed)
+}
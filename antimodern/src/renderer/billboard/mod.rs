@@ -20,26 +20,9 @@ impl Uniforms {
     fn new(scene: &entity::Scene) -> Self {
         let entity::Scene { camera, cube, .. } = scene;
 
-        let p_mat = {
-            let fovy = camera.fov / camera.aspect_ratio / 180.;
-            Mat4::perspective_lh(fovy, camera.aspect_ratio, camera.near, camera.far)
-        };
-
-        let v_mat = Mat4::look_at_lh(
-            camera.transform.position,
-            camera.transform.position + camera.transform.rotation * Vec3::Z,
-            Vec3::Y,
-        );
-
-        let m_mat = Mat4::from_scale_rotation_translation(
-            cube.transform.scale,
-            cube.transform.rotation,
-            cube.transform.position,
-        );
-
         Self {
-            mv_mat: v_mat * m_mat,
-            p_mat,
+            mv_mat: camera.view_matrix() * cube.world_matrix,
+            p_mat: camera.projection_matrix(),
         }
     }
 }
@@ -75,6 +58,7 @@ impl PipelineState {
             &bind_group_layout,
             render_target_color_format,
             render_target_depth_format,
+            renderer::depth_compare(&scene.camera),
         );
 
         let render_bundle = Self::make_render_bundle(
@@ -155,6 +139,7 @@ impl PipelineState {
         bind_group_layout: &wgpu::BindGroupLayout,
         render_target_color_format: wgpu::TextureFormat,
         render_target_depth_format: wgpu::TextureFormat,
+        depth_compare: wgpu::CompareFunction,
     ) -> wgpu::RenderPipeline {
         let shader_module = device.create_shader_module(&wgpu::include_wgsl!("main.wgsl"));
 
@@ -197,7 +182,7 @@ impl PipelineState {
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: render_target_depth_format,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
+                depth_compare,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState {
                     constant: 0,
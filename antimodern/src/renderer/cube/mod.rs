@@ -19,21 +19,8 @@ impl Uniforms {
     fn new(scene: &entity::Scene) -> Self {
         let entity::Scene { camera, cube, .. } = scene;
 
-        let proj_matrix = {
-            let fovy = camera.fov / camera.aspect_ratio / 180.;
-            Mat4::perspective_lh(fovy, camera.aspect_ratio, camera.near, camera.far)
-        };
-
-        let view_matrix = {
-            let center = camera.transform.position + camera.transform.rotation * Vec3::Z;
-            let up = Vec3::Y;
-            Mat4::look_at_lh(camera.transform.position, center, up)
-        };
-
-        let model_matrix = Mat4::from_scale_rotation_translation(cube.transform.scale, cube.transform.rotation, cube.transform.position);
-
         Self {
-            mvp_matrix: proj_matrix * view_matrix * model_matrix,
+            mvp_matrix: camera.projection_matrix() * camera.view_matrix() * cube.world_matrix,
         }
     }
 }
@@ -91,6 +78,7 @@ impl PipelineState {
             &bind_group_layout,
             render_target_color_format,
             render_target_depth_format,
+            renderer::depth_compare(&scene.camera),
         );
 
         let render_bundle = Self::make_render_bundle(
@@ -171,6 +159,7 @@ impl PipelineState {
         bind_group_layout: &wgpu::BindGroupLayout,
         render_target_color_format: wgpu::TextureFormat,
         render_target_depth_format: wgpu::TextureFormat,
+        depth_compare: wgpu::CompareFunction,
     ) -> wgpu::RenderPipeline {
         let shader_module = device.create_shader_module(&wgpu::include_wgsl!("main.wgsl"));
 
@@ -213,7 +202,7 @@ impl PipelineState {
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: render_target_depth_format,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
+                depth_compare,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState {
                     constant: 0,
@@ -1,4 +1,7 @@
-use anyhow::{Context, Ok, Result};
+use anyhow::{bail, Context, Ok, Result};
+use log::warn;
+
+use crate::entity;
 
 pub mod billboard;
 pub mod cube;
@@ -7,6 +10,7 @@ pub mod particles;
 pub struct Renderer {
     surface: wgpu::Surface,
     surface_format: wgpu::TextureFormat,
+    surface_size: winit::dpi::PhysicalSize<u32>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     depth_texture_view: wgpu::TextureView,
@@ -39,7 +43,8 @@ impl Renderer {
             .await
             .context("No device found")?;
 
-        let winit::dpi::PhysicalSize { width, height } = window.inner_size();
+        let surface_size = window.inner_size();
+        let winit::dpi::PhysicalSize { width, height } = surface_size;
 
         let surface_format = surface
             .get_preferred_format(&adapter)
@@ -52,6 +57,7 @@ impl Renderer {
         Ok(Self {
             surface,
             surface_format,
+            surface_size,
             device,
             queue,
             depth_texture_view,
@@ -121,11 +127,33 @@ impl Renderer {
         &self.device
     }
 
-    pub fn render(&self, pipeline: &impl Pipeline) {
-        let frame_buffer = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to get next surface texture");
+    pub fn render(&self, pipeline: &impl Pipeline, camera: &entity::Camera) -> Result<()> {
+        let winit::dpi::PhysicalSize { width, height } = self.surface_size;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let frame_buffer = match self.surface.get_current_texture() {
+            Result::Ok(frame_buffer) => frame_buffer,
+            Err(err @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                warn!("Surface {:?}, reconfiguring and skipping frame", err);
+                Self::configure_surface(
+                    &self.surface,
+                    &self.device,
+                    self.surface_format,
+                    width,
+                    height,
+                );
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                warn!("Timed out getting next surface texture, skipping frame");
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                bail!("Out of memory while getting next surface texture")
+            }
+        };
 
         let frame_buffer_view = frame_buffer.texture.create_view(&Default::default());
 
@@ -145,7 +173,7 @@ impl Renderer {
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                     view: &self.depth_texture_view,
                     depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
+                        load: wgpu::LoadOp::Clear(camera.far_depth()),
                         store: true,
                     }),
                     stencil_ops: None,
@@ -158,9 +186,17 @@ impl Renderer {
         self.queue.submit(Some(encoder.finish()));
 
         frame_buffer.present();
+
+        Ok(())
     }
 
     pub fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        // A minimized window reports a zero size, rendering is skipped until it's restored
+        self.surface_size = size;
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
         let Self {
             surface,
             device,
@@ -174,6 +210,15 @@ impl Renderer {
     }
 }
 
+// Reverse-Z keeps the nearest fragment by keeping the greater depth
+pub fn depth_compare(camera: &entity::Camera) -> wgpu::CompareFunction {
+    if camera.reverse_z {
+        wgpu::CompareFunction::GreaterEqual
+    } else {
+        wgpu::CompareFunction::LessEqual
+    }
+}
+
 pub trait Pipeline {
     fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>);
 }
@@ -2,10 +2,17 @@ struct Uniforms {
     mv_mat: mat4x4<f32>;
     p_mat: mat4x4<f32>;
     particle_size: f32;
+    lifetime: f32;
+    time: f32;
+    cycle: u32;
+    min_speed: f32;
+    max_speed: f32;
 };
 
 struct Instance {
     position: vec3<f32>;
+    spawn_offset: f32;
+    seed: u32;
     color: vec3<f32>;
 };
 
@@ -14,6 +21,32 @@ var<uniform> uniforms: Uniforms;
 @group(0) @binding(1)
 var<storage, read> instances: array<Instance>;
 
+// PCG hash, see "Hash Functions for GPU Rendering" by Jarzynski and Olano
+fn hash(input: u32) -> u32 {
+    let state = input * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// In [0, 1), the top 24 bits are exactly representable
+fn unit_float(value: u32) -> f32 {
+    return f32(value >> 8u) / 16777216.0;
+}
+
+// A uniformly distributed direction with a speed in the configured range
+fn random_velocity(seed: u32) -> vec3<f32> {
+    let h0 = hash(seed);
+    let h1 = hash(h0);
+    let h2 = hash(h1);
+
+    let z = unit_float(h0) * 2.0 - 1.0;
+    let phi = unit_float(h1) * 6.283185307;
+    let r = sqrt(max(1.0 - z * z, 0.0));
+    let speed = mix(uniforms.min_speed, uniforms.max_speed, unit_float(h2));
+
+    return vec3<f32>(r * cos(phi), r * sin(phi), z) * speed;
+}
+
 struct VertexOut {
     @builtin(position) position: vec4<f32>;
     @location(0) color: vec4<f32>;
@@ -26,7 +59,20 @@ fn vs_main(
 ) -> VertexOut {
     let instance = instances[instance_index];
 
-    var position = uniforms.mv_mat * vec4<f32>(instance.position, 1.0);
+    // Particles respawn at their initial position once they outlive the lifetime, or never
+    // when it's zero. Both the time and the offset are below the lifetime, so at most one more
+    // cycle has passed for this particle, and each cycle gets a new velocity
+    var age = uniforms.time + instance.spawn_offset;
+    var cycle = uniforms.cycle;
+    if (uniforms.lifetime > 0.0) {
+        let cycles = floor(age / uniforms.lifetime);
+        age = age - cycles * uniforms.lifetime;
+        cycle = cycle + u32(cycles);
+    }
+    let velocity = random_velocity(instance.seed ^ hash(cycle));
+    let particle_position = instance.position + velocity * age;
+
+    var position = uniforms.mv_mat * vec4<f32>(particle_position, 1.0);
     position += vec4<f32>(vertex_position * uniforms.particle_size, 1.0);
 
     var out: VertexOut;
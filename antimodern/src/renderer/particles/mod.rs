@@ -17,38 +17,31 @@ struct Uniforms {
     mv_mat: Mat4,
     p_mat: Mat4,
     particle_size: f32,
-    _pad0: [u8; 12],
+    lifetime: f32,
+    time: f32,
+    cycle: u32,
+    min_speed: f32,
+    max_speed: f32,
+    _pad0: [u8; 8],
 }
 
 impl Uniforms {
-    fn new(scene: &entity::Scene) -> Self {
+    fn new(scene: &entity::Scene, time: f32, cycle: u32) -> Self {
         let entity::Scene {
             camera,
             particle_system,
             ..
         } = scene;
 
-        let p_mat = {
-            let fovy = camera.fov / camera.aspect_ratio / 180.;
-            Mat4::perspective_lh(fovy, camera.aspect_ratio, camera.near, camera.far)
-        };
-
-        let v_mat = {
-            let center = camera.transform.position + camera.transform.rotation * Vec3::Z;
-            let up = Vec3::Y;
-            Mat4::look_at_lh(camera.transform.position, center, up)
-        };
-
-        let m_mat = Mat4::from_scale_rotation_translation(
-            particle_system.transform.scale,
-            particle_system.transform.rotation,
-            particle_system.transform.position,
-        );
-
         Self {
-            mv_mat: v_mat * m_mat,
-            p_mat,
+            mv_mat: camera.view_matrix() * particle_system.world_matrix,
+            p_mat: camera.projection_matrix(),
             particle_size: particle_system.particle_size,
+            lifetime: particle_system.lifetime,
+            time,
+            cycle,
+            min_speed: particle_system.min_speed,
+            max_speed: particle_system.max_speed,
             ..Default::default()
         }
     }
@@ -58,7 +51,10 @@ impl Uniforms {
 #[repr(C)]
 struct Instance {
     position: Vec3,
-    _pad0: [u8; 4],
+    spawn_offset: f32,
+    // Hashed with the respawn cycle into the velocity
+    seed: u32,
+    _pad0: [u8; 12],
     color: Vec3,
     _pad1: [u8; 4],
 }
@@ -66,6 +62,9 @@ struct Instance {
 pub struct PipelineState {
     uniform_buffer: wgpu::Buffer,
     render_bundle: wgpu::RenderBundle,
+    // Time since the start of the current respawn cycle, wrapped at the lifetime
+    time: f32,
+    cycle: u32,
 }
 
 impl PipelineState {
@@ -84,8 +83,6 @@ impl PipelineState {
         scene: &entity::Scene,
     ) -> Self {
         let uniform_buffer = Self::make_uniform_buffer(device, scene);
-        let vertex_buffer = Self::make_vertex_buffer(device);
-        let index_buffer = Self::make_index_buffer(device);
         let instance_buffer = Self::make_instance_buffer(device, scene);
 
         let bind_group_layout = Self::make_bind_group_layout(device);
@@ -100,6 +97,7 @@ impl PipelineState {
             &bind_group_layout,
             render_target_color_format,
             render_target_depth_format,
+            renderer::depth_compare(&scene.camera),
         );
 
         let render_bundle = Self::make_render_bundle(
@@ -108,14 +106,14 @@ impl PipelineState {
             render_target_depth_format,
             &render_pipeline,
             &bind_group,
-            &vertex_buffer,
-            &index_buffer,
             scene,
         );
 
         Self {
             uniform_buffer,
             render_bundle,
+            time: 0.,
+            cycle: 0,
         }
     }
 
@@ -140,12 +138,14 @@ impl PipelineState {
             particle_system, ..
         } = scene;
 
-        let unix_milli = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as _;
-        info!("Seeded RNG with {}", unix_milli);
-        let mut rng = Pcg64Mcg::seed_from_u64(unix_milli);
+        let rand_seed = particle_system.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as _
+        });
+        info!("Seeded RNG with {}", rand_seed);
+        let mut rng = Pcg64Mcg::seed_from_u64(rand_seed);
 
         let instances: Vec<_> = (0..particle_system.max_count)
             .map(|_| Instance {
@@ -154,6 +154,12 @@ impl PipelineState {
                     rng.gen_range(-0.5..0.5),
                     rng.gen_range(-0.5..0.5),
                 ) * particle_system.transform.scale,
+                spawn_offset: if particle_system.lifetime > 0. {
+                    rng.gen_range(0.0..particle_system.lifetime)
+                } else {
+                    0.
+                },
+                seed: rng.gen(),
                 color: vec3(
                     rng.gen_range(0.0..1.0),
                     rng.gen_range(0.0..1.0),
@@ -173,7 +179,7 @@ impl PipelineState {
     fn make_uniform_buffer(device: &wgpu::Device, scene: &entity::Scene) -> wgpu::Buffer {
         device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Uniform buffer"),
-            contents: bytes_of(&Uniforms::new(scene)),
+            contents: bytes_of(&Uniforms::new(scene, 0., 0)),
             usage: wgpu::BufferUsages::UNIFORM
                 | wgpu::BufferUsages::MAP_READ
                 | wgpu::BufferUsages::MAP_WRITE,
@@ -235,6 +241,7 @@ impl PipelineState {
         bind_group_layout: &wgpu::BindGroupLayout,
         render_target_color_format: wgpu::TextureFormat,
         render_target_depth_format: wgpu::TextureFormat,
+        depth_compare: wgpu::CompareFunction,
     ) -> wgpu::RenderPipeline {
         let shader_module = device.create_shader_module(&wgpu::include_wgsl!("main.wgsl"));
 
@@ -277,7 +284,7 @@ impl PipelineState {
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: render_target_depth_format,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
+                depth_compare,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState {
                     constant: 0,
@@ -296,10 +303,11 @@ impl PipelineState {
         render_target_depth_format: wgpu::TextureFormat,
         render_pipeline: &wgpu::RenderPipeline,
         bind_group: &wgpu::BindGroup,
-        vertex_buffer: &wgpu::Buffer,
-        index_buffer: &wgpu::Buffer,
         scene: &entity::Scene,
     ) -> wgpu::RenderBundle {
+        let vertex_buffer = Self::make_vertex_buffer(device);
+        let index_buffer = Self::make_index_buffer(device);
+
         let mut encoder =
             device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
                 label: None,
@@ -326,8 +334,23 @@ impl PipelineState {
         encoder.finish(&wgpu::RenderBundleDescriptor { label: None })
     }
 
-    pub fn update(&self, device: &wgpu::Device, scene: &entity::Scene) -> Result<()> {
-        let uniforms = Uniforms::new(scene);
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        scene: &entity::Scene,
+        delta_time: f32,
+    ) -> Result<()> {
+        // Wrapped at the lifetime so the time keeps its precision, the completed cycles are
+        // counted separately to seed the velocities of respawned particles
+        self.time += delta_time;
+        let lifetime = scene.particle_system.lifetime;
+        if lifetime > 0. {
+            let cycles = (self.time / lifetime).floor();
+            self.time -= cycles * lifetime;
+            self.cycle = self.cycle.wrapping_add(cycles as u32);
+        }
+
+        let uniforms = Uniforms::new(scene, self.time, self.cycle);
         debug!("{:#?}", uniforms);
 
         let uniform_buffer_slice = self.uniform_buffer.slice(..);
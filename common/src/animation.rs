@@ -0,0 +1,297 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use anyhow::{ensure, Result};
+use glam::Vec3;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Easing {
+    Linear,
+    // Holds the value until the next keyframe
+    Step,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InSine,
+    OutSine,
+    InOutSine,
+}
+
+impl Easing {
+    // Maps the progress through a segment to the interpolation factor, both from 0 to 1
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Self::InQuad => t * t,
+            Self::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Self::InOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(2) * 0.5
+                }
+            }
+            Self::InCubic => t * t * t,
+            Self::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) * 0.5
+                }
+            }
+            Self::InSine => 1.0 - (t * FRAC_PI_2).cos(),
+            Self::OutSine => (t * FRAC_PI_2).sin(),
+            Self::InOutSine => (1.0 - (t * PI).cos()) * 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LoopMode {
+    // Holds the first and last values outside of the keyframes
+    Once,
+    Repeat,
+    // Plays forwards, then backwards
+    PingPong,
+}
+
+pub trait Interpolate: Copy {
+    fn interpolate(self, other: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct Keyframe<T> {
+    // In seconds
+    pub time: f32,
+    pub value: T,
+    // Of the segment towards the next keyframe
+    pub easing: Easing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct Curve<T> {
+    // Sorted by time
+    pub keyframes: Vec<Keyframe<T>>,
+    pub loop_mode: LoopMode,
+}
+
+impl<T> Curve<T> {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.keyframes.is_empty(), "keyframes must not be empty");
+        ensure!(
+            self.keyframes
+                .windows(2)
+                .all(|keyframes| keyframes[0].time < keyframes[1].time),
+            "keyframes must be in increasing time order"
+        );
+        Ok(())
+    }
+}
+
+impl<T: Interpolate> Curve<T> {
+    // Expects at least one keyframe, in increasing time order
+    pub fn sample(&self, time: f32) -> T {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        let (start, end) = (keyframes[0].time, keyframes[last].time);
+        let length = end - start;
+        let time = match self.loop_mode {
+            _ if length <= 0.0 => start,
+            LoopMode::Once => time.clamp(start, end),
+            LoopMode::Repeat => start + (time - start).rem_euclid(length),
+            LoopMode::PingPong => {
+                let time = (time - start).rem_euclid(2.0 * length);
+                start + length - (time - length).abs()
+            }
+        };
+
+        let next = keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time)
+            .unwrap_or(last);
+        if next == 0 {
+            return keyframes[0].value;
+        }
+
+        let (k1, k2) = (&keyframes[next - 1], &keyframes[next]);
+        let t = ((time - k1.time) / (k2.time - k1.time)).clamp(0.0, 1.0);
+        k1.value.interpolate(k2.value, k1.easing.apply(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use crate::test_util::assert_approx_eq;
+
+    use super::*;
+
+    const EASINGS: [Easing; 11] = [
+        Easing::Linear,
+        Easing::Step,
+        Easing::InQuad,
+        Easing::OutQuad,
+        Easing::InOutQuad,
+        Easing::InCubic,
+        Easing::OutCubic,
+        Easing::InOutCubic,
+        Easing::InSine,
+        Easing::OutSine,
+        Easing::InOutSine,
+    ];
+
+    fn curve(loop_mode: LoopMode, easing: Easing) -> Curve<f32> {
+        Curve {
+            keyframes: vec![
+                Keyframe {
+                    time: 1.0,
+                    value: 10.0,
+                    easing,
+                },
+                Keyframe {
+                    time: 3.0,
+                    value: 20.0,
+                    easing,
+                },
+            ],
+            loop_mode,
+        }
+    }
+
+    fn constant<T>(value: T) -> Curve<T> {
+        Curve {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value,
+                easing: Easing::Linear,
+            }],
+            loop_mode: LoopMode::Once,
+        }
+    }
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for easing in EASINGS {
+            assert_approx_eq(easing.apply(0.0), 0.0);
+            assert_approx_eq(easing.apply(1.0), 1.0);
+            for t in [0.25, 0.5, 0.75] {
+                let eased = easing.apply(t);
+                assert!((0.0..=1.0).contains(&eased), "{:?} {}", easing, eased);
+            }
+        }
+        assert_approx_eq(Easing::InOutSine.apply(0.5), 0.5);
+        assert_approx_eq(Easing::InOutCubic.apply(0.5), 0.5);
+        assert_approx_eq(Easing::InQuad.apply(0.5), 0.25);
+    }
+
+    #[test]
+    fn curve_passes_through_keyframes() {
+        for easing in EASINGS {
+            let curve = curve(LoopMode::Once, easing);
+            assert_approx_eq(curve.sample(1.0), 10.0);
+            assert_approx_eq(curve.sample(3.0), 20.0);
+        }
+        assert_approx_eq(curve(LoopMode::Once, Easing::Linear).sample(2.0), 15.0);
+        assert_approx_eq(curve(LoopMode::Once, Easing::Step).sample(2.9), 10.0);
+    }
+
+    #[test]
+    fn curve_loop_modes() {
+        let once = curve(LoopMode::Once, Easing::Linear);
+        assert_approx_eq(once.sample(0.0), 10.0);
+        assert_approx_eq(once.sample(10.0), 20.0);
+
+        let repeat = curve(LoopMode::Repeat, Easing::Linear);
+        assert_approx_eq(repeat.sample(4.0), 15.0);
+        assert_approx_eq(repeat.sample(0.0), 15.0);
+
+        let ping_pong = curve(LoopMode::PingPong, Easing::Linear);
+        assert_approx_eq(ping_pong.sample(4.0), 15.0);
+        assert_approx_eq(ping_pong.sample(5.0), 10.0);
+        assert_approx_eq(ping_pong.sample(6.0), 15.0);
+        assert_approx_eq(ping_pong.sample(-1.0), 20.0);
+    }
+
+    #[test]
+    fn curve_with_one_keyframe_is_constant() {
+        let curve = Curve {
+            loop_mode: LoopMode::Repeat,
+            ..constant(vec3(1.0, 2.0, 3.0))
+        };
+        assert_eq!(curve.sample(0.0), vec3(1.0, 2.0, 3.0));
+        assert_eq!(curve.sample(5.0), vec3(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn curve_keyframes_must_be_sorted_and_not_empty() {
+        assert!(curve(LoopMode::Once, Easing::Linear).validate().is_ok());
+
+        let mut reversed = curve(LoopMode::Once, Easing::Linear);
+        reversed.keyframes.reverse();
+        assert!(reversed.validate().is_err());
+
+        let empty = Curve::<f32> {
+            keyframes: vec![],
+            loop_mode: LoopMode::Once,
+        };
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn in_out_sine_reproduces_cosine_oscillation() {
+        let keyframe = |time, value| Keyframe {
+            time,
+            value,
+            easing: Easing::InOutSine,
+        };
+        let curve = Curve {
+            keyframes: vec![
+                keyframe(0.0, 10.0),
+                keyframe(50.0, 2.0),
+                keyframe(100.0, 10.0),
+            ],
+            loop_mode: LoopMode::Repeat,
+        };
+        for time in [0.0, 12.5, 30.0, 50.0, 77.0, 140.0] {
+            let cosine = ((2.0 * PI * time * 0.01).cos() + 1.0) * 0.5 * 8.0 + 2.0;
+            assert_approx_eq(curve.sample(time), cosine);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{ensure, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct AutoExposure {
+    pub adaptation_speed: f32,
+    pub min_ev: f32,
+    pub max_ev: f32,
+    pub compensation: f32,
+}
+
+impl AutoExposure {
+    const MIDDLE_GREY: f32 = 0.18;
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.min_ev <= self.max_ev,
+            "min_ev must not be greater than max_ev"
+        );
+        ensure!(
+            self.adaptation_speed >= 0.0,
+            "adaptation_speed must not be negative"
+        );
+        Ok(())
+    }
+
+    // Moves exposure towards the one that maps the average scene luminance (log2, clamped to the
+    // EV range) to middle grey, frame rate independently
+    pub fn adapt(&self, exposure: f32, average_log_luminance: f32, delta_time: f32) -> f32 {
+        let scene_ev = average_log_luminance.clamp(self.min_ev, self.max_ev);
+        let target = Self::MIDDLE_GREY.log2() - scene_ev + self.compensation;
+        let current = exposure.max(f32::MIN_POSITIVE).log2();
+        let blend = 1.0 - (-self.adaptation_speed * delta_time).exp();
+        (current + (target - current) * blend).exp2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::assert_approx_eq;
+
+    use super::*;
+
+    fn auto_exposure() -> AutoExposure {
+        AutoExposure {
+            adaptation_speed: 1.0,
+            min_ev: -4.0,
+            max_ev: 4.0,
+            compensation: 0.0,
+        }
+    }
+
+    #[test]
+    fn adapt_converges_to_middle_grey() {
+        let auto_exposure = auto_exposure();
+        let exposure = auto_exposure.adapt(1.0, 2.0, 100.0);
+        assert_approx_eq(exposure * 2f32.exp2(), AutoExposure::MIDDLE_GREY);
+    }
+
+    #[test]
+    fn adapt_is_frame_rate_independent() {
+        let auto_exposure = auto_exposure();
+        let once = auto_exposure.adapt(1.0, 1.0, 0.5);
+        let twice = auto_exposure.adapt(auto_exposure.adapt(1.0, 1.0, 0.25), 1.0, 0.25);
+        assert_approx_eq(once, twice);
+    }
+
+    #[test]
+    fn adapt_clamps_to_ev_range_and_applies_compensation() {
+        let auto_exposure = AutoExposure {
+            compensation: 1.0,
+            ..auto_exposure()
+        };
+        let dark = auto_exposure.adapt(1.0, f32::NEG_INFINITY, 100.0);
+        assert_approx_eq(dark, AutoExposure::MIDDLE_GREY * 4f32.exp2() * 2.0);
+    }
+}
@@ -0,0 +1,245 @@
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{vec2, vec3, EulerRot, Quat, Vec2, Vec3};
+use winit::event::VirtualKeyCode;
+
+use crate::transform::Transform;
+
+// Units per second, multiplied by BOOST while shift is held
+const SPEED: f32 = 2.0;
+const BOOST: f32 = 4.0;
+// Radians per unit of mouse motion
+const LOOK_SENSITIVITY: f32 = 0.001;
+// How quickly velocity and rotation catch up with the input, higher is snappier
+const SHARPNESS: f32 = 12.0;
+// Slightly less than straight up or down, where yaw is undefined
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+const MIN_ORBIT_DISTANCE: f32 = 0.1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Mode {
+    // WASD moves along the view, QE along the world's up axis
+    #[default]
+    FreeFly,
+    // Always faces the target, WS changes the distance and ADQE circle around it
+    Orbit,
+}
+
+#[derive(Debug, Default)]
+struct Keys {
+    forward: bool,
+    back: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    boost: bool,
+}
+
+impl Keys {
+    // x is right, y is up and z is forward
+    fn direction(&self) -> Vec3 {
+        let axis = |positive: bool, negative: bool| positive as i8 as f32 - negative as i8 as f32;
+        vec3(
+            axis(self.right, self.left),
+            axis(self.up, self.down),
+            axis(self.forward, self.back),
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct CameraController {
+    mode: Mode,
+    keys: Keys,
+    // Mouse motion not yet applied to the rotation, in radians of yaw and pitch
+    look: Vec2,
+    // In the same axes as Keys::direction
+    velocity: Vec3,
+}
+
+impl CameraController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            Mode::FreeFly => Mode::Orbit,
+            Mode::Orbit => Mode::FreeFly,
+        };
+        self.velocity = Vec3::ZERO;
+    }
+
+    pub fn on_key(&mut self, keycode: VirtualKeyCode, pressed: bool) {
+        let key = match keycode {
+            VirtualKeyCode::W => &mut self.keys.forward,
+            VirtualKeyCode::S => &mut self.keys.back,
+            VirtualKeyCode::A => &mut self.keys.left,
+            VirtualKeyCode::D => &mut self.keys.right,
+            VirtualKeyCode::E => &mut self.keys.up,
+            VirtualKeyCode::Q => &mut self.keys.down,
+            VirtualKeyCode::LShift | VirtualKeyCode::RShift => &mut self.keys.boost,
+            _ => return,
+        };
+        *key = pressed;
+    }
+
+    // Key releases are missed while the window isn't focused, so forget what is held
+    pub fn release_keys(&mut self) {
+        self.keys = Keys::default();
+        self.look = Vec2::ZERO;
+    }
+
+    pub fn on_mouse_move(&mut self, (x, y): (f64, f64)) {
+        self.look += vec2(x as f32, y as f32) * LOOK_SENSITIVITY;
+    }
+
+    // target is what the orbit mode circles around
+    pub fn update(&mut self, transform: &mut Transform, target: Vec3, delta_time: f32) {
+        // Exponential smoothing covers the same fraction of the way in the same time at any
+        // frame rate
+        let smoothing = 1.0 - (-SHARPNESS * delta_time).exp();
+
+        let look = self.look * smoothing;
+        self.look -= look;
+
+        let speed = if self.keys.boost {
+            SPEED * BOOST
+        } else {
+            SPEED
+        };
+        self.velocity = self.velocity.lerp(self.keys.direction() * speed, smoothing);
+        let movement = self.velocity * delta_time;
+
+        let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::YXZ);
+        match self.mode {
+            Mode::FreeFly => {
+                let pitch = (pitch + look.y).clamp(-MAX_PITCH, MAX_PITCH);
+                transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw + look.x, pitch, roll);
+                transform.position +=
+                    transform.rotation * vec3(movement.x, 0.0, movement.z) + Vec3::Y * movement.y;
+            }
+            Mode::Orbit => {
+                let offset = target - transform.position;
+                let distance = offset.length();
+                let direction = if distance > 0.0 {
+                    offset / distance
+                } else {
+                    transform.rotation * Vec3::Z
+                };
+                let distance = (distance - movement.z).max(MIN_ORBIT_DISTANCE);
+
+                // Strafing moves along the circle at the same speed as flying would
+                let yaw = direction.x.atan2(direction.z) + look.x - movement.x / distance;
+                let pitch = ((-direction.y).asin() + look.y + movement.y / distance)
+                    .clamp(-MAX_PITCH, MAX_PITCH);
+                transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, roll);
+                transform.position = target - transform.rotation * Vec3::Z * distance;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(position: Vec3) -> Transform {
+        Transform {
+            position,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+        }
+    }
+
+    fn hold(controller: &mut CameraController, transform: &mut Transform, frame_rate: u32) {
+        for _ in 0..frame_rate {
+            controller.update(transform, Vec3::ZERO, 1.0 / frame_rate as f32);
+        }
+    }
+
+    #[test]
+    fn movement_is_independent_of_frame_rate() {
+        let positions = [30, 60, 240].map(|frame_rate| {
+            let mut controller = CameraController::new();
+            let mut transform = transform(Vec3::ZERO);
+            controller.on_key(VirtualKeyCode::W, true);
+            hold(&mut controller, &mut transform, frame_rate);
+            transform.position
+        });
+        for position in positions {
+            assert!(position.abs_diff_eq(positions[2], 0.05), "{:?}", positions);
+        }
+        assert!(positions[2].z > SPEED * 0.9, "{:?}", positions);
+    }
+
+    #[test]
+    fn boost_and_vertical_movement() {
+        let mut controller = CameraController::new();
+        let mut transform = transform(Vec3::ZERO);
+        transform.rotation = Quat::from_rotation_x(0.5);
+        controller.on_key(VirtualKeyCode::E, true);
+        controller.on_key(VirtualKeyCode::LShift, true);
+        hold(&mut controller, &mut transform, 60);
+        let position = transform.position;
+        assert!(
+            position.x.abs() < 1e-5 && position.z.abs() < 1e-5,
+            "{}",
+            position
+        );
+        assert!(position.y > SPEED * BOOST * 0.9, "{}", position);
+    }
+
+    #[test]
+    fn mouse_look_is_smoothed() {
+        let mut controller = CameraController::new();
+        let mut transform = transform(Vec3::ZERO);
+        controller.on_mouse_move((500.0, 0.0));
+
+        controller.update(&mut transform, Vec3::ZERO, 1.0 / 60.0);
+        let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        assert!(yaw > 0.0 && yaw < 0.5, "{}", yaw);
+
+        hold(&mut controller, &mut transform, 60);
+        let (yaw, _, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        assert!((yaw - 0.5).abs() < 1e-3, "{}", yaw);
+    }
+
+    #[test]
+    fn orbit_circles_target_at_constant_distance() {
+        let target = vec3(0.0, 0.0, 10.0);
+        let mut controller = CameraController::new();
+        controller.toggle_mode();
+        let mut transform = transform(vec3(3.0, 0.0, 6.0));
+        controller.on_key(VirtualKeyCode::D, true);
+        controller.on_key(VirtualKeyCode::E, true);
+        for _ in 0..60 {
+            controller.update(&mut transform, target, 1.0 / 60.0);
+            let distance = transform.position.distance(target);
+            assert!((distance - 5.0).abs() < 1e-3, "{}", distance);
+            let forward = transform.rotation * Vec3::Z;
+            let to_target = (target - transform.position).normalize();
+            assert!(forward.abs_diff_eq(to_target, 1e-3), "{}", forward);
+        }
+        assert!(transform.position.y > 0.0, "{}", transform.position);
+    }
+
+    #[test]
+    fn orbit_moves_towards_target() {
+        let target = vec3(0.0, 0.0, 10.0);
+        let mut controller = CameraController::new();
+        controller.toggle_mode();
+        let mut transform = transform(Vec3::ZERO);
+        controller.on_key(VirtualKeyCode::W, true);
+        for _ in 0..600 {
+            controller.update(&mut transform, target, 1.0 / 60.0);
+        }
+        let distance = transform.position.distance(target);
+        assert!((distance - MIN_ORBIT_DISTANCE).abs() < 1e-3, "{}", distance);
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::{ensure, Result};
+use glam::{Quat, Vec3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct CameraKeyframe {
+    // In seconds
+    pub time: f32,
+    pub position: Vec3,
+    #[cfg_attr(feature = "serde", serde(with = "crate::transform::euler_degrees"))]
+    pub rotation: Quat,
+    pub fov: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct CameraPath {
+    // Sorted by time
+    pub keyframes: Vec<CameraKeyframe>,
+    // Starts over after the last keyframe instead of holding it, make the last keyframe match the
+    // first for a seamless loop
+    pub looping: bool,
+}
+
+impl CameraPath {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.keyframes.is_empty(), "keyframes must not be empty");
+        ensure!(
+            self.keyframes
+                .windows(2)
+                .all(|keyframes| keyframes[0].time < keyframes[1].time),
+            "keyframes must be in increasing time order"
+        );
+        ensure!(
+            self.keyframes
+                .iter()
+                .all(|keyframe| keyframe.fov > 0.0 && keyframe.fov < 180.0),
+            "keyframes fov must be between 0 and 180 degrees"
+        );
+        Ok(())
+    }
+
+    // Catmull-Rom for position, slerp for rotation and linear for fov, all between the keyframes
+    // around time. Expects at least one keyframe, in increasing time order.
+    pub fn sample(&self, time: f32) -> CameraKeyframe {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        if last == 0 {
+            return keyframes[0];
+        }
+
+        let (start_time, end_time) = (keyframes[0].time, keyframes[last].time);
+        let time = if self.looping {
+            start_time + (time - start_time).rem_euclid(end_time - start_time)
+        } else {
+            time.clamp(start_time, end_time)
+        };
+
+        // The segment containing time, the last one if time is at the very end
+        let end = keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time)
+            .unwrap_or(last);
+        let (k1, k2) = (&keyframes[end - 1], &keyframes[end]);
+
+        // The neighbours only shape the tangents, the ends repeat their own keyframe
+        let k0 = &keyframes[end.saturating_sub(2)];
+        let k3 = &keyframes[(end + 1).min(last)];
+
+        // Tangents scaled by the segment duration, which keeps the speed continuous across
+        // keyframes that aren't evenly spaced in time
+        let duration = k2.time - k1.time;
+        let tangent = |before: &CameraKeyframe, after: &CameraKeyframe| {
+            (after.position - before.position) / (after.time - before.time) * duration
+        };
+        let (m1, m2) = (tangent(k0, k2), tangent(k1, k3));
+
+        let s = ((time - k1.time) / duration).clamp(0.0, 1.0);
+        let (s2, s3) = (s * s, s * s * s);
+        let position = k1.position * (2.0 * s3 - 3.0 * s2 + 1.0)
+            + m1 * (s3 - 2.0 * s2 + s)
+            + k2.position * (-2.0 * s3 + 3.0 * s2)
+            + m2 * (s3 - s2);
+
+        CameraKeyframe {
+            time,
+            position,
+            rotation: k1.rotation.slerp(k2.rotation, s),
+            fov: k1.fov + (k2.fov - k1.fov) * s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, EulerRot};
+
+    use crate::test_util::assert_approx_eq;
+
+    use super::*;
+
+    fn keyframe(time: f32, position: Vec3, yaw_degrees: f32, fov: f32) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            position,
+            rotation: Quat::from_rotation_y(yaw_degrees.to_radians()),
+            fov,
+        }
+    }
+
+    fn path(looping: bool) -> CameraPath {
+        CameraPath {
+            keyframes: vec![
+                keyframe(0.0, vec3(0.0, 0.0, 0.0), 0.0, 60.0),
+                keyframe(1.0, vec3(1.0, 0.0, 0.0), 90.0, 40.0),
+                keyframe(3.0, vec3(1.0, 2.0, 0.0), 180.0, 40.0),
+                keyframe(4.0, vec3(0.0, 0.0, 0.0), 0.0, 60.0),
+            ],
+            looping,
+        }
+    }
+
+    #[test]
+    fn path_passes_through_keyframes() {
+        let path = path(false);
+        for keyframe in &path.keyframes {
+            let sample = path.sample(keyframe.time);
+            assert!(
+                sample.position.abs_diff_eq(keyframe.position, 1e-5),
+                "{} is not {}",
+                sample.position,
+                keyframe.position
+            );
+            // q and -q are the same rotation
+            assert_approx_eq(sample.rotation.dot(keyframe.rotation).abs(), 1.0);
+            assert_approx_eq(sample.fov, keyframe.fov);
+        }
+    }
+
+    #[test]
+    fn path_velocity_is_continuous_at_keyframes() {
+        let path = path(false);
+        for time in [1.0, 3.0] {
+            let dt = 1e-2;
+            let before = (path.sample(time).position - path.sample(time - dt).position) / dt;
+            let after = (path.sample(time + dt).position - path.sample(time).position) / dt;
+            assert!(
+                before.abs_diff_eq(after, 0.1),
+                "{} is not {} at {}",
+                before,
+                after,
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn path_slerps_rotation_and_lerps_fov() {
+        let sample = path(false).sample(0.5);
+        let (yaw, _, _) = sample.rotation.to_euler(EulerRot::YXZ);
+        assert_approx_eq(yaw, 45f32.to_radians());
+        assert_approx_eq(sample.fov, 50.0);
+    }
+
+    #[test]
+    fn path_holds_ends_unless_looping() {
+        let path = path(false);
+        assert_eq!(path.sample(-1.0).position, path.keyframes[0].position);
+        assert_eq!(path.sample(10.0).position, path.keyframes[3].position);
+
+        let looping = CameraPath {
+            looping: true,
+            ..path.clone()
+        };
+        for time in [0.5, 1.5, 3.5] {
+            assert!(looping
+                .sample(time + 8.0)
+                .position
+                .abs_diff_eq(path.sample(time).position, 1e-4));
+        }
+    }
+
+    #[test]
+    fn path_with_one_keyframe_is_constant() {
+        let keyframe = keyframe(2.0, vec3(1.0, 2.0, 3.0), 30.0, 45.0);
+        for looping in [false, true] {
+            let path = CameraPath {
+                keyframes: vec![keyframe],
+                looping,
+            };
+            assert_eq!(path.sample(0.0), keyframe);
+            assert_eq!(path.sample(5.0), keyframe);
+        }
+    }
+
+    #[test]
+    fn path_keyframes_must_be_sorted_with_valid_fov() {
+        assert!(path(false).validate().is_ok());
+        assert!(CameraPath::default().validate().is_err());
+
+        let mut unsorted = path(false);
+        unsorted.keyframes.swap(1, 2);
+        assert!(unsorted.validate().is_err());
+
+        let mut fov = path(false);
+        fov.keyframes[2].fov = 180.0;
+        assert!(fov.validate().is_err());
+    }
+}
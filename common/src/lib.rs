@@ -0,0 +1,13 @@
+pub mod animation;
+pub mod auto_exposure;
+pub mod camera_controller;
+pub mod camera_path;
+pub mod luminance_meter;
+pub mod projection;
+pub mod render_graph;
+pub mod scene_graph;
+pub mod shader;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod tonemap;
+pub mod transform;
@@ -0,0 +1,54 @@
+struct Luminance {
+  log_sum: f32,
+  count: f32,
+}
+
+@group(0) @binding(0)
+var r_color_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var<storage, read_write> r_luminance: Luminance;
+
+var<workgroup> log_sums: array<f32, 256>;
+var<workgroup> counts: array<f32, 256>;
+
+// A single 16x16 workgroup where each invocation measures a 4x4 block of a 64x64 grid spread over
+// the whole texture, then the partial sums are reduced in workgroup memory
+@compute @workgroup_size(16, 16)
+fn main(
+  @builtin(local_invocation_id) local_id: vec3<u32>,
+  @builtin(local_invocation_index) index: u32,
+) {
+  let dimension = vec2<f32>(textureDimensions(r_color_texture));
+
+  var log_sum = 0.0;
+  var count = 0.0;
+  for (var y = 0u; y < 4u; y += 1u) {
+    for (var x = 0u; x < 4u; x += 1u) {
+      let cell = vec2<f32>(local_id.xy * 4u + vec2<u32>(x, y)) + 0.5;
+      let color = textureLoad(r_color_texture, vec2<i32>(cell / 64.0 * dimension), 0);
+      let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+      // Ignore the empty background, otherwise it dominates the average
+      if (luminance > 0.0001) {
+        log_sum += log2(luminance);
+        count += 1.0;
+      }
+    }
+  }
+
+  log_sums[index] = log_sum;
+  counts[index] = count;
+  workgroupBarrier();
+
+  for (var stride = 128u; stride > 0u; stride /= 2u) {
+    if (index < stride) {
+      log_sums[index] += log_sums[index + stride];
+      counts[index] += counts[index + stride];
+    }
+    workgroupBarrier();
+  }
+
+  if (index == 0u) {
+    r_luminance.log_sum = log_sums[0];
+    r_luminance.count = counts[0];
+  }
+}
@@ -0,0 +1,231 @@
+use std::{future::Future, mem::size_of, pin::Pin};
+
+use bytemuck::{Pod, Zeroable};
+use log::error;
+use smol::future::{block_on, poll_once};
+
+use crate::shader::{include_shader, ReloadablePipeline};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Luminance {
+    log_sum: f32,
+    count: f32,
+}
+
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), wgpu::BufferAsyncError>> + Send>>;
+
+// Measures the average log2 luminance of an HDR color texture. The result is read back without
+// stalling, so it lags a frame or more behind the rendered image.
+pub struct LuminanceMeter {
+    storage_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    readback: Option<MapFuture>,
+    copied: bool,
+    average_log_luminance: Option<f32>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    compute_pipeline: ReloadablePipeline<wgpu::ComputePipeline>,
+    bind_group: wgpu::BindGroup,
+}
+
+impl LuminanceMeter {
+    pub fn new(device: &wgpu::Device, color_texture_view: &wgpu::TextureView) -> Self {
+        let storage_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Luminance Storage Buffer"),
+            size: size_of::<Luminance>() as _,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Luminance Readback Buffer"),
+            size: size_of::<Luminance>() as _,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<Luminance>() as _),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_pipeline = {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+            ReloadablePipeline::new(
+                device,
+                vec![include_shader!("luminance.compute.wgsl")],
+                move |device, shader_modules| {
+                    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some("Luminance Compute Pipeline"),
+                        layout: Some(&pipeline_layout),
+                        module: &shader_modules[0],
+                        entry_point: "main",
+                    })
+                },
+            )
+        };
+
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &storage_buffer,
+            color_texture_view,
+        );
+
+        Self {
+            storage_buffer,
+            readback_buffer,
+            readback: None,
+            copied: false,
+            average_log_luminance: None,
+            bind_group_layout,
+            compute_pipeline,
+            bind_group,
+        }
+    }
+
+    pub fn recreate_bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        color_texture_view: &wgpu::TextureView,
+    ) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.storage_buffer,
+            color_texture_view,
+        );
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.compute_pipeline.reload(device);
+    }
+
+    // Average log2 luminance of the lit pixels from the latest finished readback, None until the
+    // first one finishes
+    pub fn average_log_luminance(&self) -> Option<f32> {
+        self.average_log_luminance
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        storage_buffer: &wgpu::Buffer,
+        color_texture_view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(color_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: storage_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    pub fn measure(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Luminance Compute Pass"),
+            });
+            cpass.set_pipeline(self.compute_pipeline.get());
+            cpass.set_bind_group(0, &self.bind_group, &[]);
+            cpass.dispatch(1, 1, 1);
+        }
+
+        // The readback buffer can't be written while a previous readback is still mapping it
+        self.copied = self.readback.is_none();
+        if self.copied {
+            encoder.copy_buffer_to_buffer(
+                &self.storage_buffer,
+                0,
+                &self.readback_buffer,
+                0,
+                size_of::<Luminance>() as _,
+            );
+        }
+    }
+
+    // Call after the encoder passed to measure() was submitted
+    pub fn start_readback(&mut self) {
+        if self.copied {
+            self.copied = false;
+            self.readback = Some(Box::pin(
+                self.readback_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read),
+            ));
+        }
+    }
+
+    // Blocks until the readback started for the last measurement finishes, so that every frame
+    // sees the luminance of the one before it, e.g. when recording
+    pub fn wait_for_readback(&mut self, device: &wgpu::Device) {
+        if self.readback.is_some() {
+            device.poll(wgpu::Maintain::Wait);
+            self.poll_readback(device);
+        }
+    }
+
+    pub fn poll_readback(&mut self, device: &wgpu::Device) {
+        let readback = match &mut self.readback {
+            Some(readback) => readback,
+            None => return,
+        };
+
+        device.poll(wgpu::Maintain::Poll);
+        let result = match block_on(poll_once(readback)) {
+            Some(result) => result,
+            None => return,
+        };
+        self.readback = None;
+
+        if let Err(err) = result {
+            error!("Failed to read back scene luminance: {:?}", err);
+            return;
+        }
+
+        let luminance =
+            *bytemuck::from_bytes::<Luminance>(&self.readback_buffer.slice(..).get_mapped_range());
+        self.readback_buffer.unmap();
+
+        // Nothing lit reads as -inf, which clamps to the darkest end of the EV range
+        self.average_log_luminance = Some(if luminance.count > 0.0 {
+            luminance.log_sum / luminance.count
+        } else {
+            f32::NEG_INFINITY
+        });
+    }
+}
@@ -0,0 +1,153 @@
+use glam::Mat4;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FovAxis {
+    #[default]
+    Vertical,
+    // Keeps the horizontal extent when the window gets narrower or wider
+    Horizontal,
+}
+
+// The projection parameters of a camera
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Perspective {
+    // In degrees, along fov_axis
+    pub fov: f32,
+    pub fov_axis: FovAxis,
+    pub aspect_ratio: f32,
+    pub near: f32,
+    pub far: f32,
+    // Maps near to depth 1 and far to 0, which spreads float depth precision more evenly
+    pub reverse_z: bool,
+    // Ignores far and never clips distant geometry
+    pub infinite_far: bool,
+}
+
+impl Perspective {
+    pub fn fov_y_radians(&self) -> f32 {
+        let fov = self.fov.to_radians();
+        match self.fov_axis {
+            FovAxis::Vertical => fov,
+            FovAxis::Horizontal => 2.0 * ((fov * 0.5).tan() / self.aspect_ratio).atan(),
+        }
+    }
+
+    // Left-handed with a [0, 1] depth range, as used by wgpu
+    pub fn matrix(&self) -> Mat4 {
+        let fov_y = self.fov_y_radians();
+        match (self.reverse_z, self.infinite_far) {
+            (false, false) => Mat4::perspective_lh(fov_y, self.aspect_ratio, self.near, self.far),
+            // Swapping the planes flips the depth range
+            (true, false) => Mat4::perspective_lh(fov_y, self.aspect_ratio, self.far, self.near),
+            (false, true) => Mat4::perspective_infinite_lh(fov_y, self.aspect_ratio, self.near),
+            (true, true) => {
+                Mat4::perspective_infinite_reverse_lh(fov_y, self.aspect_ratio, self.near)
+            }
+        }
+    }
+
+    // Depth the depth buffer is cleared to
+    pub fn far_depth(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Vec4};
+
+    use crate::test_util::assert_approx_eq;
+
+    use super::*;
+
+    fn perspective(fov_axis: FovAxis, reverse_z: bool, infinite_far: bool) -> Perspective {
+        Perspective {
+            fov: 90.0,
+            fov_axis,
+            aspect_ratio: 2.0,
+            near: 1.0,
+            far: 2.0,
+            reverse_z,
+            infinite_far,
+        }
+    }
+
+    fn depth(projection: Mat4, z: f32) -> f32 {
+        projection.project_point3(vec3(0.0, 0.0, z)).z
+    }
+
+    #[test]
+    fn projection_matches_known_matrix() {
+        let projection = perspective(FovAxis::Vertical, false, false).matrix();
+        let expected = Mat4::from_cols(
+            Vec4::new(0.5, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 1.0, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 2.0, 1.0),
+            Vec4::new(0.0, 0.0, -2.0, 0.0),
+        );
+        assert!(
+            projection.abs_diff_eq(expected, 1e-6),
+            "{} is not {}",
+            projection,
+            expected
+        );
+    }
+
+    #[test]
+    fn fov_is_in_degrees_and_independent_of_aspect_ratio() {
+        for aspect_ratio in [0.5, 1.0, 2.0] {
+            let projection = Perspective {
+                fov: 60.0,
+                aspect_ratio,
+                ..perspective(FovAxis::Vertical, false, false)
+            }
+            .matrix();
+            assert_approx_eq(projection.y_axis.y, 1.0 / 30f32.to_radians().tan());
+        }
+    }
+
+    #[test]
+    fn horizontal_fov_keeps_horizontal_extent() {
+        let perspective = perspective(FovAxis::Horizontal, false, false);
+        let projection = perspective.matrix();
+        assert_approx_eq(projection.x_axis.x, 1.0);
+        assert_approx_eq(projection.y_axis.y, 2.0);
+        assert_approx_eq(perspective.fov_y_radians(), 2.0 * 0.5f32.atan());
+    }
+
+    #[test]
+    fn depth_range_is_zero_to_one() {
+        let projection = perspective(FovAxis::Vertical, false, false).matrix();
+        assert_approx_eq(depth(projection, 1.0), 0.0);
+        assert_approx_eq(depth(projection, 2.0), 1.0);
+    }
+
+    #[test]
+    fn reverse_z_flips_depth_range() {
+        let perspective = perspective(FovAxis::Vertical, true, false);
+        let projection = perspective.matrix();
+        assert_approx_eq(depth(projection, 1.0), 1.0);
+        assert_approx_eq(depth(projection, 2.0), 0.0);
+        assert_eq!(perspective.far_depth(), 0.0);
+    }
+
+    #[test]
+    fn infinite_far_never_reaches_far_depth() {
+        let projection = perspective(FovAxis::Vertical, false, true).matrix();
+        assert_approx_eq(depth(projection, 1.0), 0.0);
+        assert!(depth(projection, 1e6) <= 1.0);
+        assert!(depth(projection, 10.0) < depth(projection, 100.0));
+
+        let projection = perspective(FovAxis::Vertical, true, true).matrix();
+        assert_approx_eq(depth(projection, 1.0), 1.0);
+        assert!(depth(projection, 1e6) >= 0.0);
+        assert!(depth(projection, 10.0) > depth(projection, 100.0));
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextureId(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureDescriptor {
+    pub label: String,
+    pub format: wgpu::TextureFormat,
+    // The output size divided by this, at least one pixel
+    pub divisor: u32,
+}
+
+// Targets of a pass in the order it declared them
+pub struct NodeTextures<'a, T> {
+    pub reads: Vec<&'a T>,
+    pub writes: Vec<&'a T>,
+}
+
+struct Texture {
+    descriptor: TextureDescriptor,
+    retained: bool,
+}
+
+struct Pass<N, C> {
+    name: String,
+    reads: Vec<TextureId>,
+    writes: Vec<TextureId>,
+    create: Option<C>,
+    node: Option<N>,
+}
+
+// Passes declare the textures they read and write, the graph orders the passes, creates a target
+// T for each texture and shares them between textures whose lifetimes don't overlap. Each pass
+// has a node N, created with C the first time the graph is compiled. Compile again after a resize
+// to recreate the targets and rebind the nodes. When the structure changes, clear and declare the
+// graph again, nodes are kept across that by pass name.
+pub struct RenderGraph<T, N, C> {
+    textures: Vec<Texture>,
+    passes: Vec<Pass<N, C>>,
+    order: Vec<usize>,
+    allocations: Vec<usize>,
+    targets: Vec<T>,
+    unused_nodes: HashMap<String, N>,
+}
+
+impl<T, N, C> Default for RenderGraph<T, N, C> {
+    fn default() -> Self {
+        Self {
+            textures: Vec::new(),
+            passes: Vec::new(),
+            order: Vec::new(),
+            allocations: Vec::new(),
+            targets: Vec::new(),
+            unused_nodes: HashMap::new(),
+        }
+    }
+}
+
+impl<T, N, C> RenderGraph<T, N, C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Removes all textures and passes, the nodes are reused by passes with the same name
+    pub fn clear(&mut self) {
+        for pass in self.passes.drain(..) {
+            if let Some(node) = pass.node {
+                self.unused_nodes.insert(pass.name, node);
+            }
+        }
+        self.textures.clear();
+        self.order.clear();
+        self.allocations.clear();
+        self.targets.clear();
+    }
+
+    pub fn add_texture(&mut self, descriptor: TextureDescriptor) -> TextureId {
+        self.textures.push(Texture {
+            descriptor,
+            retained: false,
+        });
+        TextureId(self.textures.len() - 1)
+    }
+
+    // Keeps the texture intact after the graph ran, for reading it outside of the graph
+    pub fn retain(&mut self, texture: TextureId) {
+        self.textures[texture.0].retained = true;
+    }
+
+    pub fn add_pass(
+        &mut self,
+        name: impl Into<String>,
+        reads: &[TextureId],
+        writes: &[TextureId],
+        create: C,
+    ) {
+        let name = name.into();
+        assert!(
+            self.passes.iter().all(|pass| pass.name != name),
+            "Render graph pass {} was added twice",
+            name
+        );
+
+        self.passes.push(Pass {
+            name,
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            create: Some(create),
+            node: None,
+        });
+    }
+
+    // create_target gets the size of the texture, nodes that already exist are passed to bind
+    pub fn compile(
+        &mut self,
+        width: u32,
+        height: u32,
+        mut create_target: impl FnMut(&TextureDescriptor, u32, u32) -> T,
+        mut bind: impl FnMut(&mut N, &NodeTextures<T>),
+        mut create: impl FnMut(C, &NodeTextures<T>) -> N,
+    ) {
+        self.order = schedule(&self.passes);
+        self.allocations = allocate(&self.textures, &self.passes, &self.order);
+
+        self.targets = Vec::new();
+        for (i, texture) in self.textures.iter().enumerate() {
+            if self.allocations[i] < self.targets.len() {
+                continue;
+            }
+            let descriptor = &texture.descriptor;
+            self.targets.push(create_target(
+                descriptor,
+                (width / descriptor.divisor).max(1),
+                (height / descriptor.divisor).max(1),
+            ));
+        }
+
+        for pass in &mut self.passes {
+            let textures = node_textures(&self.targets, &self.allocations, pass);
+            let node = match pass
+                .node
+                .take()
+                .or_else(|| self.unused_nodes.remove(&pass.name))
+            {
+                Some(mut node) => {
+                    bind(&mut node, &textures);
+                    node
+                }
+                None => create(pass.create.take().unwrap(), &textures),
+            };
+            pass.node = Some(node);
+        }
+
+        // Whatever wasn't declared again isn't part of the graph anymore
+        self.unused_nodes.clear();
+    }
+
+    pub fn target(&self, texture: TextureId) -> &T {
+        &self.targets[self.allocations[texture.0]]
+    }
+
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut N> {
+        self.passes.iter_mut().filter_map(|pass| pass.node.as_mut())
+    }
+
+    // The nodes in the order they have to run, with their targets
+    pub fn scheduled(&self) -> impl Iterator<Item = (&N, NodeTextures<'_, T>)> {
+        self.order.iter().filter_map(|&i| {
+            let pass = &self.passes[i];
+            let textures = node_textures(&self.targets, &self.allocations, pass);
+            pass.node.as_ref().map(|node| (node, textures))
+        })
+    }
+}
+
+fn node_textures<'a, T, N, C>(
+    targets: &'a [T],
+    allocations: &[usize],
+    pass: &Pass<N, C>,
+) -> NodeTextures<'a, T> {
+    let target = |texture: &TextureId| &targets[allocations[texture.0]];
+    NodeTextures {
+        reads: pass.reads.iter().map(target).collect(),
+        writes: pass.writes.iter().map(target).collect(),
+    }
+}
+
+// Orders the passes so that every pass runs after the passes writing the textures it uses.
+// Passes writing the same texture keep the order they were added in.
+fn schedule<N, C>(passes: &[Pass<N, C>]) -> Vec<usize> {
+    let mut dependencies = vec![Vec::new(); passes.len()];
+    for (i, pass) in passes.iter().enumerate() {
+        for (j, other) in passes.iter().enumerate() {
+            let writes_before = |texture: &TextureId| {
+                other.writes.contains(texture) && (j < i || !pass.writes.contains(texture))
+            };
+            if i != j && pass.reads.iter().chain(&pass.writes).any(writes_before) {
+                dependencies[i].push(j);
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(passes.len());
+    let mut scheduled = vec![false; passes.len()];
+    while order.len() < passes.len() {
+        // The first pass whose dependencies all ran, so independent passes keep the added order
+        let next = (0..passes.len())
+            .find(|&i| !scheduled[i] && dependencies[i].iter().all(|&j| scheduled[j]))
+            .expect("Render graph has a dependency cycle");
+        scheduled[next] = true;
+        order.push(next);
+    }
+    order
+}
+
+// Assigns each texture to a target, sharing targets of the same size and format between textures
+// that are never used at the same time. Returns the target index of each texture, indices are in
+// the order of the first texture using them.
+fn allocate<N, C>(textures: &[Texture], passes: &[Pass<N, C>], order: &[usize]) -> Vec<usize> {
+    // Textures nothing writes are filled before the graph runs, so they live from the start
+    let mut lifetimes = textures
+        .iter()
+        .map(|texture| (0, if texture.retained { usize::MAX } else { 0 }))
+        .collect::<Vec<_>>();
+    let mut written = vec![false; textures.len()];
+    for (position, pass) in order.iter().map(|&i| &passes[i]).enumerate() {
+        for &TextureId(texture) in &pass.writes {
+            if !written[texture] {
+                written[texture] = true;
+                lifetimes[texture].0 = position;
+            }
+        }
+        for &TextureId(texture) in pass.reads.iter().chain(&pass.writes) {
+            lifetimes[texture].1 = lifetimes[texture].1.max(position);
+        }
+    }
+
+    let mut by_start = (0..textures.len()).collect::<Vec<_>>();
+    by_start.sort_by_key(|&texture| lifetimes[texture].0);
+
+    // Descriptor of the first texture and the last position used of each target
+    let mut targets: Vec<(&TextureDescriptor, usize)> = Vec::new();
+    let mut allocations = vec![0; textures.len()];
+    for texture in by_start {
+        let descriptor = &textures[texture].descriptor;
+        let (start, end) = lifetimes[texture];
+        let reusable = targets.iter().position(|(other, other_end)| {
+            other.format == descriptor.format
+                && other.divisor == descriptor.divisor
+                && *other_end < start
+        });
+        allocations[texture] = match reusable {
+            Some(i) => {
+                targets[i].1 = end;
+                i
+            }
+            None => {
+                targets.push((descriptor, end));
+                targets.len() - 1
+            }
+        };
+    }
+
+    // Number the targets by the first texture using them, so that compile can create them in
+    // texture order
+    let mut renumbered = vec![None; targets.len()];
+    let mut count = 0;
+    for allocation in &mut allocations {
+        let number = *renumbered[*allocation].get_or_insert_with(|| {
+            count += 1;
+            count - 1
+        });
+        *allocation = number;
+    }
+
+    allocations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Targets are the descriptor and size they were created with, nodes count their binds
+    type TestGraph = RenderGraph<(String, u32, u32), (&'static str, u32), &'static str>;
+
+    fn descriptor(label: &str, divisor: u32) -> TextureDescriptor {
+        TextureDescriptor {
+            label: label.to_string(),
+            format: wgpu::TextureFormat::Rgba16Float,
+            divisor,
+        }
+    }
+
+    fn texture(label: &str, divisor: u32) -> Texture {
+        Texture {
+            descriptor: descriptor(label, divisor),
+            retained: false,
+        }
+    }
+
+    fn pass(name: &str, reads: &[usize], writes: &[usize]) -> Pass<(), ()> {
+        Pass {
+            name: name.to_string(),
+            reads: reads.iter().map(|&i| TextureId(i)).collect(),
+            writes: writes.iter().map(|&i| TextureId(i)).collect(),
+            create: None,
+            node: None,
+        }
+    }
+
+    fn compile(graph: &mut TestGraph, width: u32, height: u32) {
+        graph.compile(
+            width,
+            height,
+            |descriptor, width, height| (descriptor.label.clone(), width, height),
+            |node, _| node.1 += 1,
+            |create, _| (create, 0),
+        );
+    }
+
+    #[test]
+    fn schedule_runs_writers_before_readers() {
+        let passes = [
+            pass("composite", &[1, 2], &[3]),
+            pass("blur", &[1], &[2]),
+            pass("scene", &[], &[0]),
+            pass("bright", &[0], &[1]),
+        ];
+        assert_eq!(schedule(&passes), [2, 3, 1, 0]);
+    }
+
+    #[test]
+    fn schedule_keeps_order_of_writers() {
+        let passes = [
+            pass("clear", &[], &[0]),
+            pass("accumulate", &[0], &[0]),
+            pass("read", &[0], &[1]),
+        ];
+        assert_eq!(schedule(&passes), [0, 1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle")]
+    fn schedule_rejects_cycles() {
+        let passes = [pass("a", &[1], &[0]), pass("b", &[0], &[1])];
+        schedule(&passes);
+    }
+
+    #[test]
+    fn allocate_reuses_textures_after_their_last_use() {
+        let textures = [
+            texture("input", 1),
+            texture("a", 1),
+            texture("b", 1),
+            texture("c", 1),
+        ];
+        let passes = [
+            pass("a", &[0], &[1]),
+            pass("b", &[1], &[2]),
+            pass("c", &[2], &[3]),
+        ];
+        let order = schedule(&passes);
+        assert_eq!(allocate(&textures, &passes, &order), [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn allocate_keeps_retained_textures() {
+        let mut textures = [
+            texture("input", 1),
+            texture("a", 1),
+            texture("b", 1),
+            texture("c", 1),
+        ];
+        textures[0].retained = true;
+        let passes = [
+            pass("a", &[0], &[1]),
+            pass("b", &[1], &[2]),
+            pass("c", &[2], &[3]),
+        ];
+        let order = schedule(&passes);
+        assert_eq!(allocate(&textures, &passes, &order), [0, 1, 2, 1]);
+    }
+
+    #[test]
+    fn allocate_only_shares_matching_textures() {
+        let textures = [
+            texture("full", 1),
+            texture("half", 2),
+            texture("half again", 2),
+            texture("full again", 1),
+        ];
+        let passes = [
+            pass("a", &[], &[0]),
+            pass("b", &[0], &[1]),
+            pass("c", &[1], &[2]),
+            pass("d", &[2], &[3]),
+        ];
+        let order = schedule(&passes);
+        assert_eq!(allocate(&textures, &passes, &order), [0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn compile_rebinds_nodes_and_recreates_targets() {
+        let mut graph = TestGraph::new();
+        let color = graph.add_texture(descriptor("color", 1));
+        let half = graph.add_texture(descriptor("half", 2));
+        graph.add_pass("draw", &[], &[color], "draw");
+        graph.add_pass("downsample", &[color], &[half], "downsample");
+
+        compile(&mut graph, 5, 3);
+        assert_eq!(*graph.target(half), ("half".to_string(), 2, 1));
+        let scheduled = graph
+            .scheduled()
+            .map(|(node, textures)| (*node, textures.reads.len(), textures.writes.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(scheduled, [(("draw", 0), 0, 1), (("downsample", 0), 1, 1)]);
+
+        // Compiling again, as after a resize
+        compile(&mut graph, 1, 1);
+        assert_eq!(*graph.target(color), ("color".to_string(), 1, 1));
+        assert_eq!(*graph.target(half), ("half".to_string(), 1, 1));
+        assert!(graph.nodes_mut().all(|node| node.1 == 1));
+    }
+
+    #[test]
+    fn clear_keeps_nodes_by_name() {
+        let mut graph = TestGraph::new();
+        let color = graph.add_texture(descriptor("color", 1));
+        graph.add_pass("a", &[], &[color], "a");
+        graph.add_pass("b", &[color], &[], "b");
+        compile(&mut graph, 1, 1);
+
+        graph.clear();
+        let color = graph.add_texture(descriptor("color", 1));
+        graph.add_pass("b", &[color], &[], "new b");
+        graph.add_pass("c", &[], &[color], "c");
+        compile(&mut graph, 1, 1);
+        let nodes = graph.scheduled().map(|(node, _)| *node).collect::<Vec<_>>();
+        assert_eq!(nodes, [("c", 0), ("b", 1)]);
+
+        // a wasn't declared again
+        graph.clear();
+        graph.add_pass("a", &[], &[], "new a");
+        compile(&mut graph, 1, 1);
+        assert_eq!(graph.nodes_mut().next(), Some(&mut ("new a", 0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "added twice")]
+    fn pass_names_are_unique() {
+        let mut graph = TestGraph::new();
+        graph.add_pass("a", &[], &[], "a");
+        graph.add_pass("a", &[], &[], "a");
+    }
+}
@@ -0,0 +1,204 @@
+use glam::Mat4;
+
+use crate::transform::Transform;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+struct Node {
+    parent: Option<NodeId>,
+    // Relative to the parent
+    local: Transform,
+    world: Mat4,
+    // The local transform or parent changed since the world matrix was computed
+    dirty: bool,
+}
+
+// Nodes placed relative to their parent. World matrices are cached, and only the nodes that
+// changed and their descendants are recomputed.
+#[derive(Default)]
+pub struct SceneGraph {
+    nodes: Vec<Node>,
+    // Every node comes after its parent
+    order: Vec<usize>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, local: Transform) -> NodeId {
+        self.order.push(self.nodes.len());
+        self.nodes.push(Node {
+            parent: None,
+            local,
+            world: Mat4::IDENTITY,
+            dirty: true,
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node.0].parent
+    }
+
+    pub fn set_parent(&mut self, node: NodeId, parent: Option<NodeId>) {
+        if self.nodes[node.0].parent == parent {
+            return;
+        }
+
+        let mut ancestor = parent;
+        while let Some(id) = ancestor {
+            assert!(id != node, "Scene graph cycle");
+            ancestor = self.nodes[id.0].parent;
+        }
+
+        self.nodes[node.0].parent = parent;
+        self.nodes[node.0].dirty = true;
+
+        let nodes = &self.nodes;
+        let depth = |mut i: usize| {
+            let mut depth = 0;
+            while let Some(parent) = nodes[i].parent {
+                i = parent.0;
+                depth += 1;
+            }
+            depth
+        };
+        self.order.sort_by_key(|&i| depth(i));
+    }
+
+    pub fn set_local(&mut self, node: NodeId, local: Transform) {
+        let node = &mut self.nodes[node.0];
+        if node.local != local {
+            node.local = local;
+            node.dirty = true;
+        }
+    }
+
+    // As of the last update
+    pub fn world_matrix(&self, node: NodeId) -> Mat4 {
+        self.nodes[node.0].world
+    }
+
+    // Returns how many world matrices were recomputed
+    pub fn update(&mut self) -> usize {
+        let mut updated = vec![false; self.nodes.len()];
+        for &i in &self.order {
+            let Node { parent, dirty, .. } = self.nodes[i];
+            let parent_updated = match parent {
+                Some(parent) => updated[parent.0],
+                None => false,
+            };
+            if !dirty && !parent_updated {
+                continue;
+            }
+
+            let parent_world = parent.map_or(Mat4::IDENTITY, |parent| self.nodes[parent.0].world);
+            let node = &mut self.nodes[i];
+            node.world = parent_world * node.local.matrix();
+            node.dirty = false;
+            updated[i] = true;
+        }
+        updated.into_iter().filter(|&updated| updated).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec3, Quat, Vec3};
+
+    use crate::test_util::{assert_position, translation};
+
+    use super::*;
+
+    fn transform(position: Vec3, rotation: Quat, scale: f32) -> Transform {
+        Transform {
+            position,
+            rotation,
+            scale: Vec3::splat(scale),
+        }
+    }
+
+    #[test]
+    fn world_matrix_is_parent_world_times_local() {
+        let mut graph = SceneGraph::new();
+        let root = graph.add(transform(
+            vec3(10.0, 0.0, 0.0),
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            2.0,
+        ));
+        let child = graph.add(translation(vec3(0.0, 0.0, 1.0)));
+        let grandchild = graph.add(translation(vec3(0.0, 1.0, 0.0)));
+        graph.set_parent(grandchild, Some(child));
+        graph.set_parent(child, Some(root));
+
+        assert_eq!(graph.update(), 3);
+        assert_position(graph.world_matrix(root), vec3(10.0, 0.0, 0.0));
+        // Rotated onto +X and scaled by the root
+        assert_position(graph.world_matrix(child), vec3(12.0, 0.0, 0.0));
+        assert_position(graph.world_matrix(grandchild), vec3(12.0, 2.0, 0.0));
+        let expected = graph.world_matrix(child) * translation(vec3(0.0, 1.0, 0.0)).matrix();
+        assert!(graph.world_matrix(grandchild).abs_diff_eq(expected, 1e-5));
+    }
+
+    #[test]
+    fn update_only_recomputes_changed_subtrees() {
+        let mut graph = SceneGraph::new();
+        let root = graph.add(translation(Vec3::ZERO));
+        let child = graph.add(translation(Vec3::X));
+        let sibling = graph.add(translation(Vec3::Y));
+        graph.set_parent(child, Some(root));
+        assert_eq!(graph.update(), 3);
+        assert_eq!(graph.update(), 0);
+
+        // Setting an unchanged transform leaves the node clean
+        graph.set_local(root, translation(Vec3::ZERO));
+        assert_eq!(graph.update(), 0);
+
+        graph.set_local(child, translation(Vec3::Z));
+        assert_eq!(graph.update(), 1);
+        assert_position(graph.world_matrix(child), Vec3::Z);
+
+        graph.set_local(root, translation(Vec3::Y));
+        assert_eq!(graph.update(), 2);
+        assert_position(graph.world_matrix(child), vec3(0.0, 1.0, 1.0));
+        assert_position(graph.world_matrix(sibling), Vec3::Y);
+    }
+
+    #[test]
+    fn reparenting_moves_the_subtree() {
+        let mut graph = SceneGraph::new();
+        let a = graph.add(translation(Vec3::X));
+        let b = graph.add(translation(Vec3::Y));
+        let child = graph.add(translation(Vec3::Z));
+        graph.set_parent(child, Some(a));
+        graph.update();
+        assert_position(graph.world_matrix(child), vec3(1.0, 0.0, 1.0));
+
+        graph.set_parent(child, Some(b));
+        assert_eq!(graph.update(), 1);
+        assert_position(graph.world_matrix(child), vec3(0.0, 1.0, 1.0));
+
+        // A parent added after its child is still updated first
+        let c = graph.add(translation(Vec3::ONE));
+        graph.set_parent(b, Some(c));
+        assert_eq!(graph.update(), 3);
+        assert_position(graph.world_matrix(child), vec3(1.0, 2.0, 2.0));
+
+        graph.set_parent(child, None);
+        assert_eq!(graph.update(), 1);
+        assert_position(graph.world_matrix(child), Vec3::Z);
+    }
+
+    #[test]
+    #[should_panic(expected = "Scene graph cycle")]
+    fn parenting_to_a_descendant_panics() {
+        let mut graph = SceneGraph::new();
+        let parent = graph.add(Transform::default());
+        let child = graph.add(Transform::default());
+        graph.set_parent(child, Some(parent));
+        graph.set_parent(parent, Some(child));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+use log::{error, info};
+use pollster::FutureExt;
+
+#[macro_export]
+macro_rules! include_shader {
+    ($name:literal) => {
+        $crate::shader::Shader::new(
+            env!("CARGO_MANIFEST_DIR"),
+            file!(),
+            $name,
+            include_str!($name),
+        )
+    };
+}
+
+pub use crate::include_shader;
+
+static HOT_RELOAD: AtomicBool = AtomicBool::new(false);
+
+// Makes pipelines load their shaders from disk, including the ones built after startup, so that
+// edits saved before a pipeline is rebuilt aren't lost
+pub fn set_hot_reload(enabled: bool) {
+    HOT_RELOAD.store(enabled, Ordering::Relaxed);
+}
+
+pub fn hot_reload() -> bool {
+    HOT_RELOAD.load(Ordering::Relaxed)
+}
+
+pub struct Shader {
+    name: &'static str,
+    path: PathBuf,
+    embedded_source: &'static str,
+    modified: Option<SystemTime>,
+    // Prepended to the source, since WGSL has no includes
+    libraries: Vec<Shader>,
+}
+
+impl Shader {
+    pub fn new(
+        manifest_dir: &str,
+        source_file: &str,
+        name: &'static str,
+        embedded_source: &'static str,
+    ) -> Self {
+        // file!() is relative to the manifest inside the package being built, but absolute inside
+        // path dependencies such as this crate
+        let path = Path::new(manifest_dir)
+            .join(source_file)
+            .with_file_name(name);
+        // Taken before the pipeline reads the file, so that only files changed from now on reload
+        let modified = modified_time(&path);
+        Self {
+            name,
+            path,
+            embedded_source,
+            modified,
+            libraries: Vec::new(),
+        }
+    }
+
+    // Makes the functions of library available to this shader
+    pub fn with_library(mut self, library: Shader) -> Self {
+        self.libraries.push(library);
+        self
+    }
+
+    fn embedded_source(&self) -> String {
+        let mut source = String::new();
+        for library in &self.libraries {
+            source += &library.embedded_source();
+        }
+        source + self.embedded_source
+    }
+
+    fn read_source(&self) -> Result<String> {
+        let mut source = String::new();
+        for library in &self.libraries {
+            source += &library.read_source()?;
+        }
+        let own_source = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read shader: {}", self.path.display()))?;
+        Ok(source + &own_source)
+    }
+
+    // Whether this shader or one of its libraries changed since the last call
+    fn update_modified(&mut self) -> bool {
+        let mut changed = false;
+        for library in &mut self.libraries {
+            changed |= library.update_modified();
+        }
+        let modified = modified_time(&self.path);
+        if modified != self.modified {
+            self.modified = modified;
+            changed = true;
+        }
+        changed
+    }
+
+    fn create_shader_module(&self, device: &wgpu::Device, source: &str) -> wgpu::ShaderModule {
+        device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some(self.name),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+    }
+}
+
+type BuildPipeline<P> = dyn Fn(&wgpu::Device, &[wgpu::ShaderModule]) -> P;
+
+pub struct ReloadablePipeline<P> {
+    pipeline: P,
+    shaders: Vec<Shader>,
+    build: Box<BuildPipeline<P>>,
+}
+
+impl<P> ReloadablePipeline<P> {
+    pub fn new(
+        device: &wgpu::Device,
+        shaders: Vec<Shader>,
+        build: impl Fn(&wgpu::Device, &[wgpu::ShaderModule]) -> P + 'static,
+    ) -> Self {
+        let build: Box<BuildPipeline<P>> = Box::new(build);
+
+        // The embedded sources can be older than the files when the pipeline is rebuilt at runtime
+        let pipeline = if hot_reload() {
+            build_from_files(device, &shaders, &build).unwrap_or_else(|err| {
+                error!(
+                    "Failed to load shaders, using the embedded ones: {}: {:?}",
+                    shader_names(&shaders),
+                    err
+                );
+                build_embedded(device, &shaders, &build)
+            })
+        } else {
+            build_embedded(device, &shaders, &build)
+        };
+
+        Self {
+            pipeline,
+            shaders,
+            build,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> &P {
+        &self.pipeline
+    }
+
+    pub fn reload(&mut self, device: &wgpu::Device) {
+        let mut changed = false;
+        for shader in &mut self.shaders {
+            changed |= shader.update_modified();
+        }
+        if !changed {
+            return;
+        }
+
+        let names = shader_names(&self.shaders);
+
+        match build_from_files(device, &self.shaders, &self.build) {
+            Ok(pipeline) => {
+                self.pipeline = pipeline;
+                info!("Reloaded shaders: {}", names);
+            }
+            Err(err) => error!(
+                "Failed to reload shaders, keeping the last good pipeline: {}: {:?}",
+                names, err
+            ),
+        }
+    }
+}
+
+fn build_embedded<P>(
+    device: &wgpu::Device,
+    shaders: &[Shader],
+    build: &dyn Fn(&wgpu::Device, &[wgpu::ShaderModule]) -> P,
+) -> P {
+    let shader_modules = shaders
+        .iter()
+        .map(|shader| shader.create_shader_module(device, &shader.embedded_source()))
+        .collect::<Vec<_>>();
+
+    build(device, &shader_modules)
+}
+
+fn build_from_files<P>(
+    device: &wgpu::Device,
+    shaders: &[Shader],
+    build: &dyn Fn(&wgpu::Device, &[wgpu::ShaderModule]) -> P,
+) -> Result<P> {
+    let sources = shaders
+        .iter()
+        .map(Shader::read_source)
+        .collect::<Result<Vec<_>>>()?;
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let shader_modules = std::iter::zip(shaders, &sources)
+        .map(|(shader, source)| shader.create_shader_module(device, source))
+        .collect::<Vec<_>>();
+    let pipeline = build(device, &shader_modules);
+
+    if let Some(err) = device.pop_error_scope().block_on() {
+        bail!("{}", err);
+    }
+
+    Ok(pipeline)
+}
+
+fn shader_names(shaders: &[Shader]) -> String {
+    shaders
+        .iter()
+        .map(|shader| shader.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
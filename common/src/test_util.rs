@@ -0,0 +1,35 @@
+// Helpers for the tests of this crate and of the apps, which get them through the test-util
+// feature in their dev-dependencies
+
+use glam::{Mat4, Quat, Vec3};
+
+use crate::transform::Transform;
+
+pub fn translation(position: Vec3) -> Transform {
+    Transform {
+        position,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    }
+}
+
+#[track_caller]
+pub fn assert_position(world: Mat4, expected: Vec3) {
+    let position = world.transform_point3(Vec3::ZERO);
+    assert!(
+        position.abs_diff_eq(expected, 1e-5),
+        "{} is not {}",
+        position,
+        expected
+    );
+}
+
+#[track_caller]
+pub fn assert_approx_eq(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-5,
+        "{} is not {}",
+        actual,
+        expected
+    );
+}
@@ -0,0 +1,35 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::shader::{include_shader, Shader};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Tonemapper {
+    None,
+    Reinhard,
+    ExtendedReinhard {
+        white_point: f32,
+    },
+    #[default]
+    AcesFilmic,
+    AgX,
+}
+
+impl Tonemapper {
+    // Operator id and white point as read by tonemap() in the shader library
+    pub fn shader_params(self) -> (u32, f32) {
+        match self {
+            Self::None => (0, 1.0),
+            Self::Reinhard => (1, 1.0),
+            Self::ExtendedReinhard { white_point } => (2, white_point),
+            Self::AcesFilmic => (3, 1.0),
+            Self::AgX => (4, 1.0),
+        }
+    }
+
+    // WGSL library with tonemap(color, tonemapper, white_point), see Shader::with_library
+    pub fn shader_library() -> Shader {
+        include_shader!("tonemap.wgsl")
+    }
+}
@@ -0,0 +1,69 @@
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+  return color / (vec3<f32>(1.0) + color);
+}
+
+// Reinhard that maps white_point to 1.0 instead of approaching 1.0 asymptotically
+fn extended_reinhard(color: vec3<f32>, white_point: f32) -> vec3<f32> {
+  let numerator = color * (vec3<f32>(1.0) + color / vec3<f32>(white_point * white_point));
+  return numerator / (vec3<f32>(1.0) + color);
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+fn aces_filmic(color: vec3<f32>) -> vec3<f32> {
+  let numerator = color * (2.51 * color + vec3<f32>(0.03));
+  let denominator = color * (2.43 * color + vec3<f32>(0.59)) + vec3<f32>(0.14);
+  return clamp(numerator / denominator, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Benjamin Wrensch's minimal AgX with the default look
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+  let x2 = x * x;
+  let x4 = x2 * x2;
+  return 15.5 * x4 * x2
+    - 40.14 * x4 * x
+    + 31.96 * x4
+    - 6.868 * x2 * x
+    + 0.4298 * x2
+    + 0.1191 * x
+    - vec3<f32>(0.00232);
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+  let inset = mat3x3<f32>(
+    vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+    vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+    vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+  );
+  let outset = mat3x3<f32>(
+    vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+    vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+    vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+  );
+  let min_ev = -12.47393;
+  let max_ev = 4.026069;
+
+  var x = inset * color;
+  x = clamp(log2(max(x, vec3<f32>(1e-10))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+  x = (x - vec3<f32>(min_ev)) / (max_ev - min_ev);
+  x = outset * agx_contrast(x);
+
+  // AgX outputs display encoded values, decode them so that the result is linear like the others
+  return pow(max(x, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+// tonemapper: 0 = none, 1 = Reinhard, 2 = extended Reinhard, 3 = ACES filmic, 4 = AgX
+fn tonemap(color: vec3<f32>, tonemapper: u32, white_point: f32) -> vec3<f32> {
+  if (tonemapper == 1u) {
+    return reinhard(color);
+  }
+  if (tonemapper == 2u) {
+    return extended_reinhard(color, white_point);
+  }
+  if (tonemapper == 3u) {
+    return aces_filmic(color);
+  }
+  if (tonemapper == 4u) {
+    return agx(color);
+  }
+  return color;
+}
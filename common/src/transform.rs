@@ -0,0 +1,49 @@
+use glam::{EulerRot, Mat4, Quat, Vec3};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct Transform {
+    pub position: Vec3,
+    #[cfg_attr(feature = "serde", serde(with = "euler_degrees"))]
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.position)
+    }
+}
+
+// Euler angles in degrees as written in scene files, x is pitch, y is yaw and z is roll
+pub fn rotation_from_euler_degrees(degrees: Vec3) -> Quat {
+    Quat::from_euler(
+        EulerRot::YXZ,
+        degrees.y.to_radians(),
+        degrees.x.to_radians(),
+        degrees.z.to_radians(),
+    )
+}
+
+#[cfg(feature = "serde")]
+pub mod euler_degrees {
+    use glam::{vec3, EulerRot, Quat, Vec3};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(rotation: &Quat, serializer: S) -> Result<S::Ok, S::Error> {
+        let (y, x, z) = rotation.to_euler(EulerRot::YXZ);
+        vec3(x.to_degrees(), y.to_degrees(), z.to_degrees()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Quat, D::Error> {
+        Ok(super::rotation_from_euler_degrees(Vec3::deserialize(
+            deserializer,
+        )?))
+    }
+}
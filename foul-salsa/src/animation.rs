@@ -0,0 +1,102 @@
+use std::mem;
+
+use common::animation::Curve;
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{self, Scene};
+
+// A property of the scene driven by a curve, which overrides the value from the scene file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Animation {
+    ParticlePosition(Curve<Vec3>),
+    // Euler angles in degrees like in the transform. The angles are interpolated, not the
+    // rotation, so a curve can spin more than half a turn between keyframes.
+    ParticleRotation(Curve<Vec3>),
+    ParticleScale(Curve<Vec3>),
+    ParticleSize(Curve<f32>),
+    // Particles respawned per second, which sets the lifetime of the fixed number of particles.
+    // The renderer rescales the ages when the lifetime changes, so the rate changes smoothly.
+    EmissionRate(Curve<f32>),
+    Exposure(Curve<f32>),
+    BloomThreshold(Curve<f32>),
+}
+
+impl Animation {
+    pub fn apply(&self, scene: &mut Scene, time: f32) {
+        match self {
+            Self::ParticlePosition(curve) => {
+                scene.particle_system.transform.position = curve.sample(time)
+            }
+            Self::ParticleRotation(curve) => {
+                scene.particle_system.transform.rotation =
+                    entity::rotation_from_euler_degrees(curve.sample(time))
+            }
+            Self::ParticleScale(curve) => {
+                scene.particle_system.transform.scale = curve.sample(time)
+            }
+            Self::ParticleSize(curve) => scene.particle_system.particle_size = curve.sample(time),
+            Self::EmissionRate(curve) => {
+                let particle_system = &mut scene.particle_system;
+                particle_system.lifetime = particle_system.max_count as f32 / curve.sample(time);
+            }
+            Self::Exposure(curve) => scene.camera.exposure = curve.sample(time),
+            Self::BloomThreshold(curve) => scene.bloom_effect.threshold = curve.sample(time),
+        }
+    }
+}
+
+// Applies scene.animations at time, in seconds. Later animations win over earlier ones that
+// drive the same property.
+pub fn animate(scene: &mut Scene, time: f32) {
+    let animations = mem::take(&mut scene.animations);
+    for animation in &animations {
+        animation.apply(scene, time);
+    }
+    scene.animations = animations;
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        animation::{Easing, Keyframe, LoopMode},
+        test_util::assert_approx_eq,
+    };
+    use glam::vec3;
+
+    use super::*;
+
+    fn constant<T>(value: T) -> Curve<T> {
+        Curve {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value,
+                easing: Easing::Linear,
+            }],
+            loop_mode: LoopMode::Once,
+        }
+    }
+
+    #[test]
+    fn animate_applies_animations_to_scene() {
+        let mut scene = Scene {
+            animations: vec![
+                Animation::ParticleRotation(constant(vec3(0.0, 90.0, 0.0))),
+                Animation::ParticleSize(constant(0.5)),
+                Animation::EmissionRate(constant(50.0)),
+                Animation::BloomThreshold(constant(2.0)),
+            ],
+            ..Default::default()
+        };
+        scene.particle_system.max_count = 100;
+
+        animate(&mut scene, 1.0);
+
+        let forward = scene.particle_system.transform.rotation * Vec3::Z;
+        assert!(forward.abs_diff_eq(Vec3::X, 1e-5), "{}", forward);
+        assert_approx_eq(scene.particle_system.particle_size, 0.5);
+        assert_approx_eq(scene.particle_system.lifetime, 2.0);
+        assert_approx_eq(scene.bloom_effect.threshold, 2.0);
+        assert_eq!(scene.animations.len(), 4);
+    }
+}
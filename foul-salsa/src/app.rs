@@ -1,12 +1,14 @@
 use std::{
-    f32::consts::{PI, TAU},
     future::Future,
-    time::Instant,
+    path::PathBuf,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Ok, Result};
-use glam::{vec3, EulerRot, Quat, Vec3};
-use log::{debug, info};
+use chrono::Local;
+use common::{camera_controller::CameraController, shader};
+use glam::{Mat4, Vec3};
+use log::{debug, error, info};
 use winit::{
     dpi::PhysicalPosition,
     event::{MouseScrollDelta, VirtualKeyCode},
@@ -14,66 +16,53 @@ use winit::{
 };
 
 use crate::{
-    entity::{BloomEffect, Camera, ParticleSystem, Scene, Transform},
-    renderer::Renderer,
+    animation, capture::CaptureSource, entity::Scene, recording::Recording, renderer::Renderer,
+    scene_graph::EntityGraph,
 };
 
+const SHADER_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct App {
     new_at: Instant,
+    rendered_at: Instant,
+    shaders_checked_at: Instant,
     window: Window,
     scene: Scene,
     renderer: Renderer,
+    recording: Option<Recording>,
     cursor_locked: bool,
+    camera_controller: CameraController,
+    entity_graph: EntityGraph,
 }
 
 impl App {
-    pub async fn new(window: Window) -> Result<Self> {
+    pub async fn new(
+        window: Window,
+        mut scene: Scene,
+        recording: Option<Recording>,
+    ) -> Result<Self> {
         let new_at = Instant::now();
 
-        let scene = Scene {
-            camera: {
-                let inner_size = window.inner_size();
-                let aspect_ratio = inner_size.width as f32 / inner_size.height as f32;
-                Camera {
-                    transform: Transform {
-                        position: Vec3::ZERO,
-                        rotation: Quat::IDENTITY,
-                        ..Default::default()
-                    },
-                    fov: 60.,
-                    aspect_ratio,
-                    near: 0.1,
-                    far: 1000.,
-                    exposure: 1.0,
-                }
-            },
-            particle_system: ParticleSystem {
-                transform: Transform {
-                    position: vec3(0., 0., 10.),
-                    rotation: Quat::from_axis_angle(Vec3::X, PI * -0.25),
-                    scale: Vec3::ONE * 1.5,
-                },
-                max_count: 1000,
-                particle_size: 0.01,
-                lifetime: 0,
-                min_speed: 0.01,
-                max_speed: 1.,
-            },
-            bloom_effect: BloomEffect {
-                intensity: 1.0,
-                threshold: 1.0,
-            },
-        };
+        let inner_size = window.inner_size();
+        scene.camera.aspect_ratio = inner_size.width as f32 / inner_size.height as f32;
         info!("{:#?}", &scene);
 
+        let mut entity_graph = EntityGraph::new();
+        entity_graph.update(&mut scene);
+
         let renderer = Renderer::new(&window, &scene).await?;
 
         Ok(Self {
             new_at,
+            rendered_at: new_at,
+            shaders_checked_at: new_at,
             window,
             scene,
             renderer,
+            recording,
             cursor_locked: false,
+            camera_controller: CameraController::new(),
+            entity_graph,
         })
     }
 
@@ -84,7 +73,9 @@ impl App {
             return;
         }
 
-        self.scene.camera.aspect_ratio = size.width as f32 / size.height as f32;
+        if size.width > 0 && size.height > 0 {
+            self.scene.camera.aspect_ratio = size.width as f32 / size.height as f32;
+        }
         self.renderer.resize(size.width, size.height);
     }
 
@@ -94,37 +85,71 @@ impl App {
         self.cursor_locked = true;
     }
 
+    pub fn on_key_down(&mut self, keycode: VirtualKeyCode) {
+        if self.cursor_locked {
+            self.camera_controller.on_key(keycode, true);
+        }
+    }
+
     pub fn on_key_up(&mut self, keycode: VirtualKeyCode) {
+        self.camera_controller.on_key(keycode, false);
         match keycode {
             VirtualKeyCode::Escape => {
                 self.window.set_cursor_grab(false).unwrap();
                 self.window.set_cursor_visible(true);
                 self.cursor_locked = false;
+                self.camera_controller.release_keys();
             }
-            VirtualKeyCode::K => {
-                self.scene.camera.exposure += 0.1;
-                info!("Camera exposure increased: {}", self.scene.camera.exposure);
-            }
-            VirtualKeyCode::J => {
-                self.scene.camera.exposure -= 0.1;
-                info!("Camera exposure decreased: {}", self.scene.camera.exposure);
+            VirtualKeyCode::O => {
+                self.camera_controller.toggle_mode();
+                info!("Camera mode: {:?}", self.camera_controller.mode());
             }
+            VirtualKeyCode::K => self.adjust_exposure(0.1),
+            VirtualKeyCode::J => self.adjust_exposure(-0.1),
+            VirtualKeyCode::P => self.save_capture(CaptureSource::Surface),
+            VirtualKeyCode::H => self.save_capture(CaptureSource::HdrColor),
             _ => (),
         }
     }
 
+    // Steps exposure compensation in EV while auto exposure drives the exposure
+    fn adjust_exposure(&mut self, delta: f32) {
+        let camera = &mut self.scene.camera;
+        match &mut camera.auto_exposure {
+            Some(auto_exposure) => {
+                auto_exposure.compensation += delta;
+                info!("Exposure compensation: {}", auto_exposure.compensation);
+            }
+            None => {
+                camera.exposure += delta;
+                info!("Camera exposure: {}", camera.exposure);
+            }
+        }
+    }
+
+    fn save_capture(&self, source: CaptureSource) {
+        let result = self.renderer.capture_frame(source).and_then(|capture| {
+            let path = PathBuf::from(format!(
+                "screenshot-{}.{}",
+                Local::now().format("%Y%m%d-%H%M%S%.3f"),
+                capture.extension()
+            ));
+            capture.save(&path)?;
+            Ok(path)
+        });
+
+        match result {
+            Result::Ok(path) => info!("Screenshot saved: {}", path.display()),
+            Err(err) => error!("Failed to save screenshot: {:?}", err),
+        }
+    }
+
     pub fn on_mouse_move(&mut self, (x, y): (f64, f64)) {
         if !self.cursor_locked {
             return;
         };
 
-        let mut rotation = self.scene.camera.transform.rotation.to_euler(EulerRot::YXZ);
-        rotation.0 += x as f32 * 0.001;
-        rotation.1 = (rotation.1 + y as f32 * 0.001).clamp(PI * -0.5, PI * 0.5);
-        debug!("rotation: {:?}", rotation);
-
-        self.scene.camera.transform.rotation =
-            Quat::from_euler(glam::EulerRot::YXZ, rotation.0, rotation.1, rotation.2);
+        self.camera_controller.on_mouse_move((x, y));
     }
 
     pub fn on_mouse_scroll(&mut self, delta: winit::event::MouseScrollDelta) {
@@ -139,15 +164,95 @@ impl App {
         self.scene.camera.fov = (self.scene.camera.fov + y * -0.1).clamp(30., 120.);
     }
 
-    pub fn render(&mut self) -> impl Future<Output = ()> {
-        let now = Instant::now().duration_since(self.new_at).as_millis() as f32 * 0.001;
+    pub fn render(&mut self) -> Result<impl Future<Output = ()>> {
+        let now = match &self.recording {
+            Some(recording) => recording.time(),
+            None => Instant::now().duration_since(self.new_at).as_millis() as f32 * 0.001,
+        };
 
-        self.scene.particle_system.transform.rotation = Quat::from_axis_angle(Vec3::Y, now * 0.01);
+        // Checking every shader file for changes is too slow to do every frame
+        if shader::hot_reload() && self.shaders_checked_at.elapsed() >= SHADER_WATCH_INTERVAL {
+            self.shaders_checked_at = Instant::now();
+            self.renderer.reload_shaders();
+        }
 
-        let scale = ((TAU * now * 0.01).cos() + 1.0) * 0.5;
-        let scale = scale * 8.0 + 2.0;
-        self.scene.particle_system.transform.scale = Vec3::ONE * scale as f32;
+        animation::animate(&mut self.scene, now);
+
+        let rendered_at = Instant::now();
+        let delta_time = match &self.recording {
+            Some(recording) => recording.timestep(),
+            None => rendered_at.duration_since(self.rendered_at).as_secs_f32(),
+        };
+        self.rendered_at = rendered_at;
+
+        // The camera moves in its parent's space, where the orbit target has to be too
+        let parent_world = self
+            .scene
+            .camera
+            .parent
+            .map_or(Mat4::IDENTITY, |parent| self.scene.world_matrix(parent));
+        let target = parent_world.inverse().transform_point3(
+            self.scene
+                .particle_system
+                .world_matrix
+                .transform_point3(Vec3::ZERO),
+        );
+
+        let camera = &mut self.scene.camera;
+        match &camera.path {
+            Some(path) => {
+                let keyframe = path.sample(now);
+                camera.transform.position = keyframe.position;
+                camera.transform.rotation = keyframe.rotation;
+                camera.fov = keyframe.fov;
+            }
+            None => self
+                .camera_controller
+                .update(&mut camera.transform, target, delta_time),
+        }
+        self.entity_graph.update(&mut self.scene);
+
+        // Readbacks lag by a varying number of frames, so recordings keep the fixed exposure
+        let camera = &mut self.scene.camera;
+        if let (Some(auto_exposure), Some(average_log_luminance), None) = (
+            camera.auto_exposure,
+            self.renderer.average_log_luminance(),
+            &self.recording,
+        ) {
+            camera.exposure =
+                auto_exposure.adapt(camera.exposure, average_log_luminance, delta_time);
+        }
+
+        self.renderer.render(&self.scene, delta_time)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn is_recording_finished(&self) -> bool {
+        self.recording
+            .as_ref()
+            .is_some_and(|recording| recording.is_finished())
+    }
+
+    pub fn record_frame(&mut self) -> Result<()> {
+        let recording = match &mut self.recording {
+            Some(recording) => recording,
+            None => return Ok(()),
+        };
+
+        let path = recording.frame_path();
+        self.renderer
+            .capture_frame(CaptureSource::Surface)?
+            .save(&path)?;
+        debug!("Recorded frame: {}", path.display());
+
+        recording.advance();
+        if recording.is_finished() {
+            info!("Recorded {} frames", recording.frame_count());
+        }
 
-        self.renderer.render(&self.scene)
+        Ok(())
     }
 }
@@ -1,6 +1,6 @@
 struct Uniforms {
   threshold: f32,
-  intensity: f32,
+  knee: f32,
 }
 
 @group(0) @binding(0)
@@ -12,8 +12,16 @@ var r_sampler: sampler;
 
 @fragment
 fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
-  var color = textureSample(r_texture, r_sampler, tex_coord);
-  color = pow(color, vec4<f32>(r_uniforms.intensity)) - vec4<f32>(r_uniforms.threshold);
-  color = max(color, vec4<f32>(0.0));
-  return color;
+  let color = textureSample(r_texture, r_sampler, tex_coord);
+  // Soft-knee threshold on luminance, keeps the hue and fades in over [threshold - knee, threshold + knee].
+  // Without a knee it's a hard cutoff at the threshold.
+  let brightness = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+  let threshold = r_uniforms.threshold;
+  let knee = r_uniforms.knee;
+  let contribution = select(
+    step(threshold, brightness),
+    smoothstep(threshold - knee, threshold + knee, brightness),
+    knee > 0.0
+  );
+  return vec4<f32>(color.rgb * contribution, color.a);
 }
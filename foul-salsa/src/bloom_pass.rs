@@ -1,80 +1,89 @@
 use std::mem::size_of;
 
 use bytemuck::{bytes_of, Pod, Zeroable};
-
-use crate::{entity::Scene, frame_buffers::FrameBuffers, samplers::Samplers};
-
-pub struct BloomRenderer {
-    bright_pass: BrightPass,
-    downscale: DownScale,
-    blur_pass: BlurPass,
-    combine: Combine,
-}
-
-impl BloomRenderer {
-    pub const STAGING_BUFFER_CHUNK_SIZE: wgpu::BufferAddress = size_of::<BrightUniforms>() as _;
-
-    pub fn new(device: &wgpu::Device, frame_buffers: &FrameBuffers, samplers: &Samplers) -> Self {
-        let bright_pass = BrightPass::new(device, frame_buffers, samplers);
-        let downscale = DownScale::new(device, frame_buffers, samplers);
-        let blur_pass = BlurPass::new(device, frame_buffers, samplers);
-        let combine = Combine::new(device, frame_buffers, samplers);
-
-        Self {
-            bright_pass,
-            downscale,
-            blur_pass,
-            combine,
-        }
-    }
-
-    pub fn recreate_bind_group(
-        &mut self,
-        device: &wgpu::Device,
-        frame_buffers: &FrameBuffers,
-        samplers: &Samplers,
-    ) {
-        self.bright_pass
-            .recreate_bind_group(device, frame_buffers, samplers);
-        self.downscale
-            .recreate_bind_group(device, frame_buffers, samplers);
-        self.blur_pass
-            .recreate_bind_group(device, frame_buffers, samplers);
-        self.combine
-            .recreate_bind_group(device, frame_buffers, samplers);
-    }
-
-    pub fn update(
-        &self,
-        device: &wgpu::Device,
-        staging_belt: &mut wgpu::util::StagingBelt,
-        encoder: &mut wgpu::CommandEncoder,
-        scene: &Scene,
-    ) {
-        self.bright_pass
-            .update(device, staging_belt, encoder, scene);
-    }
-
-    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, frame_buffers: &FrameBuffers) {
-        self.bright_pass.draw(encoder, frame_buffers);
-        self.downscale.draw(encoder, frame_buffers);
-        self.blur_pass.draw(encoder, frame_buffers);
-        self.combine.draw(encoder, frame_buffers);
-    }
+use common::shader::{include_shader, ReloadablePipeline};
+
+use crate::{
+    entity::Scene,
+    frame_buffer::FrameBuffer,
+    render_graph::{Node, NodeTextures, RenderGraph, TextureDescriptor, TextureId},
+    samplers::Samplers,
+};
+
+pub const STAGING_BUFFER_CHUNK_SIZE: wgpu::BufferAddress = size_of::<BrightUniforms>() as _;
+
+const NUM_LEVELS: u32 = 4;
+
+// Declares the bloom passes reading color, returns the texture the blurred levels are added into
+pub fn add_bloom(graph: &mut RenderGraph, color: TextureId) -> TextureId {
+    let bright = graph.add_texture(TextureDescriptor {
+        label: "Bloom Bright Texture".to_string(),
+        format: FrameBuffer::BLOOM_FORMAT,
+        divisor: 4,
+    });
+    graph.add_pass(
+        "Bloom Bright Pass",
+        &[color],
+        &[bright],
+        |device, samplers, textures| Box::new(BrightPass::new(device, samplers, textures)),
+    );
+
+    // Each level is blurred back and forth between its two textures, ending in the first one
+    let levels = (0..NUM_LEVELS)
+        .map(|i| {
+            let divisor = 4 * (2 * (i + 1)); // 8, 16, 24, 32
+            [0, 1].map(|j| {
+                graph.add_texture(TextureDescriptor {
+                    label: format!("Bloom Blur Texture {} {}", i, j),
+                    format: FrameBuffer::BLOOM_FORMAT,
+                    divisor,
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    let blurred = levels.iter().map(|level| level[0]).collect::<Vec<_>>();
+    let scaled = levels.iter().map(|level| level[1]).collect::<Vec<_>>();
+
+    graph.add_pass(
+        "Bloom Down Scale",
+        &[bright],
+        &scaled,
+        |device, samplers, textures| Box::new(DownScale::new(device, samplers, textures)),
+    );
+    graph.add_pass(
+        "Bloom Blur",
+        &scaled,
+        &levels.concat(),
+        |device, samplers, textures| Box::new(BlurPass::new(device, samplers, textures)),
+    );
+
+    let bloom = graph.add_texture(TextureDescriptor {
+        label: "Bloom Texture".to_string(),
+        format: FrameBuffer::BLOOM_FORMAT,
+        divisor: 1,
+    });
+    graph.add_pass(
+        "Bloom Combine",
+        &blurred,
+        &[bloom],
+        |device, samplers, textures| Box::new(Combine::new(device, samplers, textures)),
+    );
+
+    bloom
 }
 
 #[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
 #[repr(C)]
 struct BrightUniforms {
-    intensity: f32,
     threshold: f32,
+    knee: f32,
 }
 
 impl BrightUniforms {
     fn new(scene: &Scene) -> Self {
         Self {
-            intensity: scene.bloom_effect.intensity,
             threshold: scene.bloom_effect.threshold,
+            knee: scene.bloom_effect.knee,
         }
     }
 }
@@ -83,14 +92,11 @@ struct BrightPass {
     bright_uniform_buffer: wgpu::Buffer,
     bright_bind_group: wgpu::BindGroup,
     bright_bind_group_layout: wgpu::BindGroupLayout,
-    bright_render_pipeline: wgpu::RenderPipeline,
+    bright_render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
 }
 
 impl BrightPass {
-    pub fn new(device: &wgpu::Device, frame_buffers: &FrameBuffers, samplers: &Samplers) -> Self {
-        let vertex_shader_module =
-            device.create_shader_module(&wgpu::include_wgsl!("fullscreen_vs.wgsl"));
-
+    pub fn new(device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) -> Self {
         let bright_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Bloom Uniform Buffer"),
             size: size_of::<BrightUniforms>() as _,
@@ -134,10 +140,10 @@ impl BrightPass {
             });
 
         let bright_bind_group = Self::create_bright_bind_group(
-            &device,
+            device,
             &bright_bind_group_layout,
             &bright_uniform_buffer,
-            &frame_buffers.color_texture_view,
+            &textures.reads[0].texture_view,
             &samplers.bilinear,
         );
 
@@ -148,35 +154,43 @@ impl BrightPass {
                 push_constant_ranges: &[],
             });
 
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("bloom_fs_bright.wgsl"));
+            let target_format = textures.writes[0].format;
 
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[FrameBuffers::BLOOM_FORMAT.into()],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen_vs.wgsl"),
+                    include_shader!("bloom_fs_bright.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState {
+                            topology: wgpu::PrimitiveTopology::TriangleList,
+                            strip_index_format: None,
+                            front_face: wgpu::FrontFace::Ccw,
+                            cull_mode: Some(wgpu::Face::Back),
+                            unclipped_depth: false,
+                            polygon_mode: wgpu::PolygonMode::Fill,
+                            conservative: false,
+                        },
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
         Self {
@@ -213,30 +227,28 @@ impl BrightPass {
             ],
         })
     }
+}
 
-    pub fn recreate_bind_group(
-        &mut self,
-        device: &wgpu::Device,
-        frame_buffers: &FrameBuffers,
-        samplers: &Samplers,
-    ) {
+impl Node for BrightPass {
+    fn bind(&mut self, device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) {
         self.bright_bind_group = Self::create_bright_bind_group(
             device,
             &self.bright_bind_group_layout,
             &self.bright_uniform_buffer,
-            &frame_buffers.color_texture_view,
+            &textures.reads[0].texture_view,
             &samplers.bilinear,
         );
     }
 
-    pub fn update(
-        &self,
+    fn update(
+        &mut self,
         device: &wgpu::Device,
         staging_belt: &mut wgpu::util::StagingBelt,
         encoder: &mut wgpu::CommandEncoder,
         scene: &Scene,
+        _: f32,
     ) {
-        let bloom_uniforms = BrightUniforms::new(&scene);
+        let bloom_uniforms = BrightUniforms::new(scene);
         staging_belt
             .write_buffer(
                 encoder,
@@ -248,12 +260,16 @@ impl BrightPass {
             .copy_from_slice(bytes_of(&bloom_uniforms));
     }
 
-    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, frame_buffers: &FrameBuffers) {
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.bright_render_pipeline.reload(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Bloom Bright Render Pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachment {
-                    view: &frame_buffers.bright_texture_view,
+                    view: &textures.writes[0].texture_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -263,7 +279,7 @@ impl BrightPass {
                 depth_stencil_attachment: None,
             });
             rpass.set_bind_group(0, &self.bright_bind_group, &[]);
-            rpass.set_pipeline(&self.bright_render_pipeline);
+            rpass.set_pipeline(self.bright_render_pipeline.get());
             rpass.draw(0..3, 0..1);
         }
     }
@@ -272,14 +288,11 @@ impl BrightPass {
 struct DownScale {
     bind_group: wgpu::BindGroup,
     bind_group_layout: wgpu::BindGroupLayout,
-    render_pipeline: wgpu::RenderPipeline,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
 }
 
 impl DownScale {
-    pub fn new(device: &wgpu::Device, frame_buffers: &FrameBuffers, samplers: &Samplers) -> Self {
-        let vertex_shader_module =
-            device.create_shader_module(&wgpu::include_wgsl!("fullscreen_vs.wgsl"));
-
+    pub fn new(device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) -> Self {
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
             entries: &[
@@ -303,9 +316,9 @@ impl DownScale {
         });
 
         let bind_group = Self::create_bind_group(
-            &device,
+            device,
             &bind_group_layout,
-            &frame_buffers.bright_texture_view,
+            &textures.reads[0].texture_view,
             &samplers.bilinear,
         );
 
@@ -316,35 +329,43 @@ impl DownScale {
                 push_constant_ranges: &[],
             });
 
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("draw_texture_fs.wgsl"));
+            let target_format = textures.writes[0].format;
 
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[FrameBuffers::BLOOM_FORMAT.into()],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen_vs.wgsl"),
+                    include_shader!("draw_texture_fs.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState {
+                            topology: wgpu::PrimitiveTopology::TriangleList,
+                            strip_index_format: None,
+                            front_face: wgpu::FrontFace::Ccw,
+                            cull_mode: Some(wgpu::Face::Back),
+                            unclipped_depth: false,
+                            polygon_mode: wgpu::PolygonMode::Fill,
+                            conservative: false,
+                        },
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
         Self {
@@ -375,27 +396,28 @@ impl DownScale {
             ],
         })
     }
+}
 
-    pub fn recreate_bind_group(
-        &mut self,
-        device: &wgpu::Device,
-        frame_buffers: &FrameBuffers,
-        samplers: &Samplers,
-    ) {
+impl Node for DownScale {
+    fn bind(&mut self, device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) {
         self.bind_group = Self::create_bind_group(
             device,
             &self.bind_group_layout,
-            &frame_buffers.bright_texture_view,
+            &textures.reads[0].texture_view,
             &samplers.bilinear,
         );
     }
 
-    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, frame_buffers: &FrameBuffers) {
-        for buf in &frame_buffers.bloom_blur_buffers {
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        for frame_buffer in &textures.writes {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Bloom Scale Down Render Pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachment {
-                    view: &buf[1].texture_view,
+                    view: &frame_buffer.texture_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -405,7 +427,7 @@ impl DownScale {
                 depth_stencil_attachment: None,
             });
             rpass.set_bind_group(0, &self.bind_group, &[]);
-            rpass.set_pipeline(&self.render_pipeline);
+            rpass.set_pipeline(self.render_pipeline.get());
             rpass.draw(0..3, 0..1);
         }
     }
@@ -414,14 +436,11 @@ impl DownScale {
 struct BlurPass {
     blur_bind_group_layout: wgpu::BindGroupLayout,
     blur_bind_groups: Vec<[wgpu::BindGroup; 2]>,
-    blur_render_pipeline: wgpu::RenderPipeline,
+    blur_render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
 }
 
 impl BlurPass {
-    pub fn new(device: &wgpu::Device, frame_buffers: &FrameBuffers, samplers: &Samplers) -> Self {
-        let vertex_shader_module =
-            device.create_shader_module(&wgpu::include_wgsl!("fullscreen_vs.wgsl"));
-
+    pub fn new(device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) -> Self {
         let blur_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: None,
@@ -445,25 +464,8 @@ impl BlurPass {
                 ],
             });
 
-        let blur_bind_groups = (&frame_buffers.bloom_blur_buffers)
-            .into_iter()
-            .map(|buffers| {
-                [
-                    Self::create_blur_bind_group(
-                        device,
-                        &blur_bind_group_layout,
-                        &buffers[0].texture_view,
-                        &samplers.bilinear,
-                    ),
-                    Self::create_blur_bind_group(
-                        device,
-                        &blur_bind_group_layout,
-                        &buffers[1].texture_view,
-                        &samplers.bilinear,
-                    ),
-                ]
-            })
-            .collect::<Vec<_>>();
+        let blur_bind_groups =
+            Self::create_blur_bind_groups(device, &blur_bind_group_layout, samplers, textures);
 
         let blur_render_pipeline = {
             let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -472,35 +474,43 @@ impl BlurPass {
                 push_constant_ranges: &[],
             });
 
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("bloom_fs_blur.wgsl"));
+            let target_format = textures.writes[0].format;
 
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[frame_buffers.bloom_blur_buffers[0][0].format.into()],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen_vs.wgsl"),
+                    include_shader!("bloom_fs_blur.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState {
+                            topology: wgpu::PrimitiveTopology::TriangleList,
+                            strip_index_format: None,
+                            front_face: wgpu::FrontFace::Ccw,
+                            cull_mode: Some(wgpu::Face::Back),
+                            unclipped_depth: false,
+                            polygon_mode: wgpu::PolygonMode::Fill,
+                            conservative: false,
+                        },
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
         Self {
@@ -532,36 +542,50 @@ impl BlurPass {
         })
     }
 
-    pub fn recreate_bind_group(
-        &mut self,
+    // The writes are the two textures of each level in turn
+    fn create_blur_bind_groups(
         device: &wgpu::Device,
-        frame_buffers: &FrameBuffers,
+        layout: &wgpu::BindGroupLayout,
         samplers: &Samplers,
-    ) {
-        self.blur_bind_groups = (&frame_buffers.bloom_blur_buffers)
-            .into_iter()
+        textures: &NodeTextures,
+    ) -> Vec<[wgpu::BindGroup; 2]> {
+        textures
+            .writes
+            .chunks(2)
             .map(|buffers| {
                 [
                     Self::create_blur_bind_group(
                         device,
-                        &self.blur_bind_group_layout,
+                        layout,
                         &buffers[0].texture_view,
                         &samplers.bilinear,
                     ),
                     Self::create_blur_bind_group(
                         device,
-                        &self.blur_bind_group_layout,
+                        layout,
                         &buffers[1].texture_view,
                         &samplers.bilinear,
                     ),
                 ]
             })
-            .collect::<Vec<_>>();
+            .collect::<Vec<_>>()
+    }
+}
+
+impl Node for BlurPass {
+    fn bind(&mut self, device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) {
+        self.blur_bind_groups =
+            Self::create_blur_bind_groups(device, &self.blur_bind_group_layout, samplers, textures);
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.blur_render_pipeline.reload(device);
     }
 
-    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, frame_buffers: &FrameBuffers) {
-        let attachment_views = (&frame_buffers.bloom_blur_buffers)
-            .into_iter()
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        let attachment_views = textures
+            .writes
+            .chunks(2)
             .map(|buffers| [&buffers[0].texture_view, &buffers[1].texture_view]);
 
         for (attachment_views, bind_groups) in
@@ -581,7 +605,7 @@ impl BlurPass {
                     depth_stencil_attachment: None,
                 });
                 rpass.set_bind_group(0, &bind_groups[(i + 1) % 2], &[]);
-                rpass.set_pipeline(&self.blur_render_pipeline);
+                rpass.set_pipeline(self.blur_render_pipeline.get());
                 rpass.draw(0..3, 0..1);
             }
         }
@@ -591,14 +615,11 @@ impl BlurPass {
 struct Combine {
     bind_group_layout: wgpu::BindGroupLayout,
     bind_groups: Vec<wgpu::BindGroup>,
-    render_pipeline: wgpu::RenderPipeline,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
 }
 
 impl Combine {
-    pub fn new(device: &wgpu::Device, frame_buffers: &FrameBuffers, samplers: &Samplers) -> Self {
-        let vertex_shader_module =
-            device.create_shader_module(&wgpu::include_wgsl!("fullscreen_vs.wgsl"));
-
+    pub fn new(device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) -> Self {
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
             entries: &[
@@ -621,13 +642,14 @@ impl Combine {
             ],
         });
 
-        let bind_groups = (&frame_buffers.bloom_blur_buffers)
-            .into_iter()
+        let bind_groups = textures
+            .reads
+            .iter()
             .map(|buf| {
                 Self::create_bind_group(
                     device,
                     &bind_group_layout,
-                    &buf[0].texture_view,
+                    &buf.texture_view,
                     &samplers.bilinear,
                 )
             })
@@ -640,50 +662,58 @@ impl Combine {
                 push_constant_ranges: &[],
             });
 
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("draw_texture_fs.wgsl"));
+            let target_format = textures.writes[0].format;
 
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[wgpu::ColorTargetState {
-                        format: frame_buffers.bloom_buffer.format,
-                        blend: Some(wgpu::BlendState {
-                            color: wgpu::BlendComponent {
-                                src_factor: wgpu::BlendFactor::One,
-                                dst_factor: wgpu::BlendFactor::One,
-                                operation: wgpu::BlendOperation::Add,
-                            },
-                            alpha: wgpu::BlendComponent {
-                                src_factor: wgpu::BlendFactor::One,
-                                dst_factor: wgpu::BlendFactor::One,
-                                operation: wgpu::BlendOperation::Add,
-                            },
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen_vs.wgsl"),
+                    include_shader!("draw_texture_fs.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[wgpu::ColorTargetState {
+                                format: target_format,
+                                blend: Some(wgpu::BlendState {
+                                    color: wgpu::BlendComponent {
+                                        src_factor: wgpu::BlendFactor::One,
+                                        dst_factor: wgpu::BlendFactor::One,
+                                        operation: wgpu::BlendOperation::Add,
+                                    },
+                                    alpha: wgpu::BlendComponent {
+                                        src_factor: wgpu::BlendFactor::One,
+                                        dst_factor: wgpu::BlendFactor::One,
+                                        operation: wgpu::BlendOperation::Add,
+                                    },
+                                }),
+                                write_mask: wgpu::ColorWrites::ALL,
+                            }],
                         }),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    }],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
+                        primitive: wgpu::PrimitiveState {
+                            topology: wgpu::PrimitiveTopology::TriangleList,
+                            strip_index_format: None,
+                            front_face: wgpu::FrontFace::Ccw,
+                            cull_mode: Some(wgpu::Face::Back),
+                            unclipped_depth: false,
+                            polygon_mode: wgpu::PolygonMode::Fill,
+                            conservative: false,
+                        },
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
         Self {
@@ -714,28 +744,30 @@ impl Combine {
             ],
         })
     }
+}
 
-    pub fn recreate_bind_group(
-        &mut self,
-        device: &wgpu::Device,
-        frame_buffers: &FrameBuffers,
-        samplers: &Samplers,
-    ) {
-        self.bind_groups = (&frame_buffers.bloom_blur_buffers)
-            .into_iter()
+impl Node for Combine {
+    fn bind(&mut self, device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures) {
+        self.bind_groups = textures
+            .reads
+            .iter()
             .map(|buf| {
                 Self::create_bind_group(
                     device,
                     &self.bind_group_layout,
-                    &buf[0].texture_view,
+                    &buf.texture_view,
                     &samplers.bilinear,
                 )
             })
             .collect::<Vec<_>>()
     }
 
-    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, frame_buffers: &FrameBuffers) {
-        for (i, bind_group) in (&self.bind_groups).into_iter().enumerate() {
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        for (i, bind_group) in self.bind_groups.iter().enumerate() {
             let load_op = if i == 0 {
                 wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
             } else {
@@ -744,7 +776,7 @@ impl Combine {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Bloom Combine Render Pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachment {
-                    view: &frame_buffers.bloom_buffer.texture_view,
+                    view: &textures.writes[0].texture_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: load_op,
@@ -753,8 +785,8 @@ impl Combine {
                 }],
                 depth_stencil_attachment: None,
             });
-            rpass.set_bind_group(0, &bind_group, &[]);
-            rpass.set_pipeline(&self.render_pipeline);
+            rpass.set_bind_group(0, bind_group, &[]);
+            rpass.set_pipeline(self.render_pipeline.get());
             rpass.draw(0..3, 0..1);
         }
     }
@@ -0,0 +1,158 @@
+use std::{fs::File, io::BufWriter, num::NonZeroU32, path::Path};
+
+use anyhow::{bail, ensure, Ok, Result};
+use image::{codecs::hdr::HdrEncoder, ColorType, Rgb};
+use smol::block_on;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptureSource {
+    Surface,
+    HdrColor,
+}
+
+pub enum Capture {
+    Ldr {
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    },
+    Hdr {
+        width: u32,
+        height: u32,
+        rgb: Vec<Rgb<f32>>,
+    },
+}
+
+impl Capture {
+    pub fn ldr(
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        data: Vec<u8>,
+    ) -> Result<Self> {
+        let mut rgba = data;
+        match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {}
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                rgba.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+            }
+            _ => bail!("Unsupported capture format: {:?}", format),
+        }
+
+        Ok(Self::Ldr {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    pub fn hdr(
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        rgba16f: Vec<u8>,
+    ) -> Result<Self> {
+        ensure!(
+            format == wgpu::TextureFormat::Rgba16Float,
+            "Unsupported HDR capture format: {:?}",
+            format
+        );
+
+        let rgb = rgba16f
+            .chunks_exact(8)
+            .map(|pixel| {
+                let channel =
+                    |i: usize| half::f16::from_le_bytes([pixel[i * 2], pixel[i * 2 + 1]]).to_f32();
+                Rgb([channel(0), channel(1), channel(2)])
+            })
+            .collect();
+
+        Ok(Self::Hdr { width, height, rgb })
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ldr { .. } => "png",
+            Self::Hdr { .. } => "hdr",
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        match self {
+            Self::Ldr {
+                width,
+                height,
+                rgba,
+            } => image::save_buffer(path, rgba, *width, *height, ColorType::Rgba8)?,
+            Self::Hdr { width, height, rgb } => {
+                let writer = BufWriter::new(File::create(path)?);
+                HdrEncoder::new(writer).encode(rgb, *width as _, *height as _)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>> {
+    let bytes_per_pixel = format.describe().block_size as u32;
+    let unpadded_bytes_per_row = width * bytes_per_pixel;
+    let padded_bytes_per_row = {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        unpadded_bytes_per_row.div_ceil(align) * align
+    };
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: (padded_bytes_per_row * height) as _,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+
+    queue.submit(Some(encoder.finish()));
+
+    let buffer_slice = buffer.slice(..);
+    let mapping = buffer_slice.map_async(wgpu::MapMode::Read);
+    device.poll(wgpu::Maintain::Wait);
+    block_on(mapping)?;
+
+    let data = buffer_slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as _)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as _])
+        .copied()
+        .collect();
+
+    buffer.unmap();
+
+    Ok(data)
+}
@@ -1,5 +1,9 @@
 struct Uniforms {
   exposure: f32,
+  bloom_intensity: f32,
+  tonemapper: u32,
+  white_point: f32,
+  encode_srgb: u32,
 }
 
 struct VertexOut {
@@ -38,10 +42,22 @@ var r_bloom_texture: texture_2d<f32>;
 @group(0) @binding(3)
 var r_linear_sampler: sampler;
 
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+  let low = color * 12.92;
+  let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+  return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// tonemap() is prepended from common/src/tonemap.wgsl
 @fragment
 fn fs_main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
   var dimension = textureDimensions(r_texture);
-  var color = textureLoad(r_texture, vec2<i32>(vec2<f32>(dimension) * tex_coord), 0);
-  color += textureSample(r_bloom_texture, r_linear_sampler, tex_coord);
-  return pow(color * r_uniforms.exposure, vec4<f32>(2.2));
+  var color = textureLoad(r_texture, vec2<i32>(vec2<f32>(dimension) * tex_coord), 0).rgb;
+  color += textureSample(r_bloom_texture, r_linear_sampler, tex_coord).rgb * r_uniforms.bloom_intensity;
+  color = tonemap(color * r_uniforms.exposure, r_uniforms.tonemapper, r_uniforms.white_point);
+  // sRGB surfaces encode on write, linear ones need it done here
+  if (r_uniforms.encode_srgb != 0u) {
+    color = linear_to_srgb(color);
+  }
+  return vec4<f32>(color, 1.0);
 }
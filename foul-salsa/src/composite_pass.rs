@@ -1,28 +1,44 @@
 use std::mem::size_of;
 
 use bytemuck::{bytes_of, Pod, Zeroable};
+use common::shader::{include_shader, ReloadablePipeline};
 
-use crate::{entity::Scene, frame_buffers::FrameBuffers, samplers::Samplers, surface::Surface};
+use crate::{
+    entity::{Scene, Tonemapper},
+    frame_buffer::FrameBuffer,
+    samplers::Samplers,
+    surface::Surface,
+};
 
 #[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
 #[repr(C)]
 struct CompositeUniforms {
     exposure: f32,
+    bloom_intensity: f32,
+    tonemapper: u32,
+    white_point: f32,
+    encode_srgb: u32,
 }
 
 impl CompositeUniforms {
-    fn new(scene: &Scene) -> Self {
+    fn new(scene: &Scene, encode_srgb: bool) -> Self {
+        let (tonemapper, white_point) = scene.camera.tonemapper.shader_params();
         Self {
             exposure: scene.camera.exposure,
+            bloom_intensity: scene.bloom_effect.intensity,
+            tonemapper,
+            white_point,
+            encode_srgb: encode_srgb as _,
         }
     }
 }
 
 pub struct CompositeRenderer {
+    encode_srgb: bool,
     uniform_buffer: wgpu::Buffer,
     bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
-    render_pipeline: wgpu::RenderPipeline,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
 }
 
 impl CompositeRenderer {
@@ -31,7 +47,8 @@ impl CompositeRenderer {
     pub fn new(
         device: &wgpu::Device,
         samplers: &Samplers,
-        frame_buffers: &FrameBuffers,
+        color: &FrameBuffer,
+        bloom: &FrameBuffer,
         surface: &Surface,
     ) -> Self {
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -90,7 +107,8 @@ impl CompositeRenderer {
             device,
             &bind_group_layout,
             &uniform_buffer,
-            frame_buffers,
+            color,
+            bloom,
             samplers,
         );
 
@@ -101,37 +119,44 @@ impl CompositeRenderer {
                 push_constant_ranges: &[],
             });
 
-            let shader_module = device.create_shader_module(&wgpu::include_wgsl!("composite.wgsl"));
+            let target_format = surface.texture_format;
 
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader_module,
-                    entry_point: "vs_main",
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader_module,
-                    entry_point: "fs_main",
-                    targets: &[surface.texture_format.into()],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
+            ReloadablePipeline::new(
+                device,
+                vec![include_shader!("composite.wgsl").with_library(Tonemapper::shader_library())],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "vs_main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[0],
+                            entry_point: "fs_main",
+                            targets: &[target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState {
+                            topology: wgpu::PrimitiveTopology::TriangleList,
+                            strip_index_format: None,
+                            front_face: wgpu::FrontFace::Ccw,
+                            cull_mode: Some(wgpu::Face::Back),
+                            unclipped_depth: false,
+                            polygon_mode: wgpu::PolygonMode::Fill,
+                            conservative: false,
+                        },
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
         Self {
+            encode_srgb: !surface.texture_format.describe().srgb,
             uniform_buffer,
             bind_group_layout,
             bind_group,
@@ -143,7 +168,8 @@ impl CompositeRenderer {
         device: &wgpu::Device,
         layout: &wgpu::BindGroupLayout,
         uniform_buffer: &wgpu::Buffer,
-        frame_buffers: &FrameBuffers,
+        color: &FrameBuffer,
+        bloom: &FrameBuffer,
         samplers: &Samplers,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -156,13 +182,11 @@ impl CompositeRenderer {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&frame_buffers.color_texture_view),
+                    resource: wgpu::BindingResource::TextureView(&color.texture_view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: wgpu::BindingResource::TextureView(
-                        &frame_buffers.bloom_buffer.texture_view,
-                    ),
+                    resource: wgpu::BindingResource::TextureView(&bloom.texture_view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 3,
@@ -175,18 +199,24 @@ impl CompositeRenderer {
     pub fn recreate_bind_group(
         &mut self,
         device: &wgpu::Device,
-        frame_buffers: &FrameBuffers,
+        color: &FrameBuffer,
+        bloom: &FrameBuffer,
         samplers: &Samplers,
     ) {
         self.bind_group = Self::create_bind_group(
             device,
             &self.bind_group_layout,
             &self.uniform_buffer,
-            frame_buffers,
+            color,
+            bloom,
             samplers,
         );
     }
 
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
     pub fn update(
         &self,
         device: &wgpu::Device,
@@ -194,7 +224,7 @@ impl CompositeRenderer {
         encoder: &mut wgpu::CommandEncoder,
         scene: &Scene,
     ) {
-        let composite_uniforms = CompositeUniforms::new(&scene);
+        let composite_uniforms = CompositeUniforms::new(scene, self.encode_srgb);
 
         staging_belt
             .write_buffer(
@@ -225,7 +255,7 @@ impl CompositeRenderer {
             depth_stencil_attachment: None,
         });
         rpass.set_bind_group(0, &self.bind_group, &[]);
-        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_pipeline(self.render_pipeline.get());
         rpass.draw(0..3, 0..1);
     }
 }
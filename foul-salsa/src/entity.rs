@@ -1,41 +1,219 @@
-use glam::{Quat, Vec3};
+use common::projection::Perspective;
+use glam::Mat4;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, Default)]
-pub struct Transform {
-    pub position: Vec3,
-    pub rotation: Quat,
-    pub scale: Vec3,
+use crate::animation::Animation;
+
+pub use common::{
+    auto_exposure::AutoExposure,
+    camera_path::CameraPath,
+    projection::FovAxis,
+    tonemap::Tonemapper,
+    transform::{rotation_from_euler_degrees, Transform},
+};
+
+// Names an entity of the scene, for parenting
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Entity {
+    Camera,
+    ParticleSystem,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+impl Entity {
+    pub const ALL: [Entity; 2] = [Entity::Camera, Entity::ParticleSystem];
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Camera {
+    // Relative to the parent
     pub transform: Transform,
+    #[serde(default)]
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    #[serde(skip)]
+    pub world_matrix: Mat4,
+    // In degrees, along fov_axis
     pub fov: f32,
+    #[serde(default)]
+    pub fov_axis: FovAxis,
+    #[serde(skip)]
     pub aspect_ratio: f32,
     pub near: f32,
     pub far: f32,
+    // Maps near to depth 1 and far to 0, which spreads float depth precision more evenly
+    #[serde(default)]
+    pub reverse_z: bool,
+    // Ignores far and never clips distant geometry
+    #[serde(default)]
+    pub infinite_far: bool,
     pub exposure: f32,
+    #[serde(default)]
+    pub tonemapper: Tonemapper,
+    #[serde(default)]
+    pub auto_exposure: Option<AutoExposure>,
+    // Drives the transform and fov instead of the input while set
+    #[serde(default)]
+    pub path: Option<CameraPath>,
+}
+
+impl Camera {
+    // The camera looks along +Z of its world matrix, scale is ignored
+    pub fn view_matrix(&self) -> Mat4 {
+        let (_, rotation, translation) = self.world_matrix.to_scale_rotation_translation();
+        Mat4::from_rotation_translation(rotation, translation).inverse()
+    }
+
+    pub fn perspective(&self) -> Perspective {
+        Perspective {
+            fov: self.fov,
+            fov_axis: self.fov_axis,
+            aspect_ratio: self.aspect_ratio,
+            near: self.near,
+            far: self.far,
+            reverse_z: self.reverse_z,
+            infinite_far: self.infinite_far,
+        }
+    }
+
+    pub fn projection_matrix(&self) -> Mat4 {
+        self.perspective().matrix()
+    }
+
+    // Depth the depth buffer is cleared to
+    pub fn far_depth(&self) -> f32 {
+        self.perspective().far_depth()
+    }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ParticleSystem {
+    // Relative to the parent
     pub transform: Transform,
+    #[serde(default)]
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    #[serde(skip)]
+    pub world_matrix: Mat4,
     pub max_count: u32,
     pub particle_size: f32,
-    pub lifetime: u32,
+    // In seconds, particles never respawn when it's 0
+    pub lifetime: f32,
+    // In units per second
     pub min_speed: f32,
     pub max_speed: f32,
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct BloomEffect {
     pub intensity: f32,
     pub threshold: f32,
+    // 0 is the hard cutoff of scenes from before the knee
+    #[serde(default)]
+    pub knee: f32,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Scene {
     pub camera: Camera,
     pub particle_system: ParticleSystem,
     pub bloom_effect: BloomEffect,
+    #[serde(default)]
+    pub animations: Vec<Animation>,
+}
+
+impl Scene {
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        match entity {
+            Entity::Camera => self.camera.parent,
+            Entity::ParticleSystem => self.particle_system.parent,
+        }
+    }
+
+    pub fn world_matrix(&self, entity: Entity) -> Mat4 {
+        match entity {
+            Entity::Camera => self.camera.world_matrix,
+            Entity::ParticleSystem => self.particle_system.world_matrix,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::{vec3, EulerRot, Quat, Vec3};
+
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera {
+            transform: Transform {
+                scale: Vec3::ONE,
+                ..Default::default()
+            },
+            fov: 90.0,
+            fov_axis: FovAxis::Vertical,
+            aspect_ratio: 2.0,
+            near: 1.0,
+            far: 2.0,
+            reverse_z: false,
+            infinite_far: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn view_moves_camera_to_origin_looking_along_z() {
+        let mut camera = camera();
+        camera.transform.position = vec3(1.0, 2.0, 3.0);
+        camera.transform.rotation = Quat::from_rotation_y(FRAC_PI_2);
+        camera.world_matrix = camera.transform.matrix();
+        let view = camera.view_matrix();
+        let ahead = view.transform_point3(vec3(6.0, 2.0, 3.0));
+        assert!(ahead.abs_diff_eq(vec3(0.0, 0.0, 5.0), 1e-5), "{}", ahead);
+        let origin = view.transform_point3(vec3(1.0, 2.0, 3.0));
+        assert!(origin.abs_diff_eq(Vec3::ZERO, 1e-5), "{}", origin);
+    }
+
+    #[test]
+    fn view_matches_look_at_without_roll() {
+        let mut camera = camera();
+        camera.transform.position = vec3(-2.0, 0.5, 4.0);
+        camera.transform.rotation = Quat::from_euler(EulerRot::YXZ, 0.7, -0.4, 0.0);
+        camera.world_matrix = camera.transform.matrix();
+        let Transform {
+            position, rotation, ..
+        } = camera.transform;
+        let view = camera.view_matrix();
+        let look_at = Mat4::look_at_lh(position, position + rotation * Vec3::Z, Vec3::Y);
+        assert!(
+            view.abs_diff_eq(look_at, 1e-5),
+            "{} is not {}",
+            view,
+            look_at
+        );
+    }
+
+    #[test]
+    fn view_ignores_scale_inherited_from_parent() {
+        let mut camera = camera();
+        camera.transform.position = vec3(1.0, 2.0, 3.0);
+        camera.transform.rotation = Quat::from_rotation_y(0.3);
+        camera.world_matrix = camera.transform.matrix();
+        let expected = camera.view_matrix();
+        camera.transform.scale = Vec3::splat(4.0);
+        camera.world_matrix = camera.transform.matrix();
+        let view = camera.view_matrix();
+        assert!(
+            view.abs_diff_eq(expected, 1e-5),
+            "{} is not {}",
+            view,
+            expected
+        );
+    }
 }
@@ -0,0 +1,63 @@
+pub struct FrameBuffer {
+    pub texture: wgpu::Texture,
+    pub texture_view: wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FrameBuffer {
+    pub const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+    pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+    pub const BLOOM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+    pub fn new(
+        device: &wgpu::Device,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        // Depth buffers are only attached, color buffers are also sampled and captured
+        let is_depth = format.describe().sample_type == wgpu::TextureSampleType::Depth;
+        let (usage, aspect) = if is_depth {
+            (
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+                wgpu::TextureAspect::DepthOnly,
+            )
+        } else {
+            (
+                wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::COPY_SRC,
+                wgpu::TextureAspect::All,
+            )
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            aspect,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            texture_view,
+            format,
+            width,
+            height,
+        }
+    }
+}
@@ -1,28 +1,51 @@
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use log::debug;
+use common::shader;
+use log::{debug, error};
 use smol::{block_on, LocalExecutor};
 use winit::{
     event::{DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
 };
 
-use crate::app::App;
+use crate::{app::App, args::Args, recording::Recording};
 
+mod animation;
 mod app;
+mod args;
 mod bloom_pass;
+mod capture;
 mod composite_pass;
 mod entity;
-mod frame_buffers;
+mod frame_buffer;
 mod particle_pass;
+mod recording;
+mod render_graph;
 mod renderer;
 mod samplers;
+mod scene_file;
+mod scene_graph;
 mod surface;
 
 fn main() -> Result<()> {
     env_logger::init();
 
+    let args = Args::parse()?;
+    shader::set_hot_reload(args.hot_reload_shaders);
+
+    let mut scene = match &args.scene {
+        Some(path) => scene_file::load(path)?,
+        None => scene_file::default_scene(),
+    };
+    if args.seed.is_some() {
+        scene.particle_system.seed = args.seed;
+    }
+    let recording = args
+        .record
+        .map(|dir| Recording::new(dir, args.record_frames, args.record_fps))
+        .transpose()?;
+
     let executer = LocalExecutor::new();
 
     let event_loop = EventLoop::new();
@@ -37,7 +60,7 @@ fn main() -> Result<()> {
 
     let mut last_render_inst = Instant::now();
 
-    let mut app = block_on(App::new(window))?;
+    let mut app = block_on(App::new(window, scene, recording))?;
 
     event_loop.run(move |e, _, control_flow| {
         debug!("{:#?}", e);
@@ -54,6 +77,15 @@ fn main() -> Result<()> {
                     button: MouseButton::Left,
                     ..
                 } => app.on_mouse_up(),
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(keycode),
+                            ..
+                        },
+                    ..
+                } => app.on_key_down(keycode),
                 WindowEvent::KeyboardInput {
                     input:
                         KeyboardInput {
@@ -73,17 +105,34 @@ fn main() -> Result<()> {
             Event::MainEventsCleared => {
                 while executer.try_tick() {}
 
-                let target_frame_interval = Duration::from_secs_f64(1.0 / 60.0);
-                let elapsed_from_last_draw = last_render_inst.elapsed();
-                if target_frame_interval > elapsed_from_last_draw {
-                    let wait = target_frame_interval - elapsed_from_last_draw;
-                    *control_flow = ControlFlow::WaitUntil(Instant::now() + wait);
-                    return;
+                // Recording advances a fixed timestep per frame, so render as fast as possible
+                if !app.is_recording() {
+                    let target_frame_interval = Duration::from_secs_f64(1.0 / 60.0);
+                    let elapsed_from_last_draw = last_render_inst.elapsed();
+                    if target_frame_interval > elapsed_from_last_draw {
+                        let wait = target_frame_interval - elapsed_from_last_draw;
+                        *control_flow = ControlFlow::WaitUntil(Instant::now() + wait);
+                        return;
+                    }
                 }
 
                 last_render_inst = Instant::now();
 
-                executer.spawn(app.render()).detach();
+                match app.render() {
+                    Ok(staging_belt_recall) => executer.spawn(staging_belt_recall).detach(),
+                    Err(err) => {
+                        error!("Failed to render: {:?}", err);
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                }
+
+                if let Err(err) = app.record_frame() {
+                    error!("Failed to record frame: {:?}", err);
+                    *control_flow = ControlFlow::Exit;
+                } else if app.is_recording_finished() {
+                    *control_flow = ControlFlow::Exit;
+                }
             }
             _ => (),
         }
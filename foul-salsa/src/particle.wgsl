@@ -6,6 +6,8 @@ struct Uniforms {
 
 struct Instance {
   position: vec3<f32>,
+  age: f32,
+  velocity: vec3<f32>,
   color: vec3<f32>,
 }
 
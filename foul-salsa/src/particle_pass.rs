@@ -1,13 +1,19 @@
 use std::{mem::size_of, time::SystemTime};
 
 use bytemuck::{bytes_of, cast_slice, Pod, Zeroable};
-use glam::{const_vec3, vec3, Mat4, Vec3, Vec4};
+use common::shader::{include_shader, ReloadablePipeline};
+use glam::{const_vec3, vec2, vec3, Mat4, Vec2, Vec3, Vec4};
 use log::info;
 use rand::prelude::*;
 use rand_pcg::Pcg64Mcg;
 use wgpu::util::DeviceExt;
 
-use crate::{entity::Scene, frame_buffers::FrameBuffers};
+use crate::{
+    entity::{ParticleSystem, Scene},
+    frame_buffer::FrameBuffer,
+    render_graph::{Node, NodeTextures},
+    samplers::Samplers,
+};
 
 const QUAD_VERTICES: [Vec3; 4] = [
     const_vec3!([-0.5, -0.5, 0.]),
@@ -16,6 +22,7 @@ const QUAD_VERTICES: [Vec3; 4] = [
     const_vec3!([0.5, 0.5, 0.]),
 ];
 const QUAD_INDICES: [u16; 6] = [0, 2, 1, 1, 2, 3];
+const SIMULATION_WORKGROUP_SIZE: u32 = 64;
 
 #[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
 #[repr(C)]
@@ -34,27 +41,34 @@ impl ParticleUniforms {
             ..
         } = scene;
 
-        let p_mat = {
-            let fovy = camera.fov / camera.aspect_ratio / 180.;
-            Mat4::perspective_lh(fovy, camera.aspect_ratio, camera.near, camera.far)
-        };
-
-        let v_mat = {
-            let center = camera.transform.position + camera.transform.rotation * Vec3::Z;
-            let up = Vec3::Y;
-            Mat4::look_at_lh(camera.transform.position, center, up)
-        };
+        Self {
+            mv_mat: camera.view_matrix() * particle_system.world_matrix,
+            p_mat: camera.projection_matrix(),
+            particle_size: particle_system.particle_size,
+            ..Default::default()
+        }
+    }
+}
 
-        let m_mat = Mat4::from_scale_rotation_translation(
-            particle_system.transform.scale,
-            particle_system.transform.rotation,
-            particle_system.transform.position,
-        );
+#[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
+#[repr(C)]
+struct SimulationUniforms {
+    delta_time: f32,
+    lifetime: f32,
+    speed_range: Vec2,
+    seed: u32,
+    age_scale: f32,
+    _pad0: [u8; 8],
+}
 
+impl SimulationUniforms {
+    fn new(particle_system: &ParticleSystem, delta_time: f32, seed: u32, age_scale: f32) -> Self {
         Self {
-            mv_mat: v_mat * m_mat,
-            p_mat,
-            particle_size: particle_system.particle_size,
+            delta_time,
+            lifetime: particle_system.lifetime,
+            speed_range: vec2(particle_system.min_speed, particle_system.max_speed),
+            seed,
+            age_scale,
             ..Default::default()
         }
     }
@@ -63,17 +77,32 @@ impl ParticleUniforms {
 #[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
 #[repr(C)]
 struct ParticleInstance {
-    position: Vec4,
+    position: Vec3,
+    age: f32,
+    velocity: Vec3,
+    _pad0: [u8; 4],
     color: Vec4,
 }
 
 pub struct ParticleRenderer {
     particle_uniform_buffer: wgpu::Buffer,
-    particle_render_bundle: wgpu::RenderBundle,
+    simulation_uniform_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
+    simulation_bind_group: wgpu::BindGroup,
+    simulation_pipeline: ReloadablePipeline<wgpu::ComputePipeline>,
+    instance_count: u32,
+    far_depth: f32,
+    simulation_step: u32,
+    // Of the last update, to notice when it changes
+    lifetime: f32,
 }
 
 impl ParticleRenderer {
-    pub const STAGING_BUFFER_CHUNK_SIZE: wgpu::BufferAddress = size_of::<ParticleUniforms>() as _;
+    pub const STAGING_BUFFER_CHUNK_SIZE: wgpu::BufferAddress =
+        (size_of::<ParticleUniforms>() + size_of::<SimulationUniforms>()) as _;
 
     pub fn new(device: &wgpu::Device, scene: &Scene) -> Self {
         let particle_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
@@ -83,202 +112,294 @@ impl ParticleRenderer {
             mapped_at_creation: false,
         });
 
-        let particle_render_bundle = {
-            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Particle Vertex Buffer"),
-                contents: bytes_of(&QUAD_VERTICES),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Particle Index Buffer"),
-                contents: bytes_of(&QUAD_INDICES),
-                usage: wgpu::BufferUsages::INDEX,
-            });
+        let simulation_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Simulation Uniform Buffer"),
+            size: size_of::<SimulationUniforms>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
-            let instance_buffer = {
-                let rand_seed = SystemTime::now()
+        let instance_buffer = {
+            let rand_seed = scene.particle_system.seed.unwrap_or_else(|| {
+                SystemTime::now()
                     .duration_since(SystemTime::UNIX_EPOCH)
                     .unwrap()
-                    .as_millis() as _;
-
-                let mut rng = Pcg64Mcg::seed_from_u64(rand_seed);
-                info!("Seeded RNG with {}", rand_seed);
-
-                let instances: Vec<_> = (0..scene.particle_system.max_count)
-                    .map(|_| {
-                        let position = {
-                            let mut v = vec3(
-                                rng.gen_range(0.0..1.0),
-                                rng.gen_range(0.0..1.0),
-                                rng.gen_range(0.0..1.0),
-                            );
-                            v -= 0.5;
-
-                            (v, 1.0).into()
-                        };
-                        let color = {
-                            let mut v = vec3(
-                                rng.gen_range(0.0..1.0),
-                                rng.gen_range(0.0..1.0),
-                                rng.gen_range(0.0..1.0),
-                            );
-                            v = v.normalize();
-                            v *= 10.0;
-
-                            (v, 1.0).into()
-                        };
-                        ParticleInstance { position, color }
-                    })
-                    .collect();
+                    .as_millis() as _
+            });
 
-                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Instance Buffer"),
-                    contents: cast_slice(instances.as_slice()),
-                    usage: wgpu::BufferUsages::STORAGE,
+            let mut rng = Pcg64Mcg::seed_from_u64(rand_seed);
+            info!("Seeded RNG with {}", rand_seed);
+
+            let lifetime = scene.particle_system.lifetime;
+            let instances: Vec<_> = (0..scene.particle_system.max_count)
+                .map(|_| {
+                    let position = {
+                        let mut v = vec3(
+                            rng.gen_range(0.0..1.0),
+                            rng.gen_range(0.0..1.0),
+                            rng.gen_range(0.0..1.0),
+                        );
+                        v -= 0.5;
+                        v
+                    };
+                    // Spread initial ages so that particles don't all respawn on the same frame
+                    let age = if lifetime > 0.0 {
+                        rng.gen_range(0.0..lifetime)
+                    } else {
+                        0.0
+                    };
+                    let velocity = {
+                        let v = vec3(
+                            rng.gen_range(-1.0..1.0),
+                            rng.gen_range(-1.0..1.0),
+                            rng.gen_range(-1.0..1.0),
+                        );
+                        v.normalize_or_zero()
+                            * rng.gen_range(
+                                scene.particle_system.min_speed..=scene.particle_system.max_speed,
+                            )
+                    };
+                    let color = {
+                        let mut v = vec3(
+                            rng.gen_range(0.0..1.0),
+                            rng.gen_range(0.0..1.0),
+                            rng.gen_range(0.0..1.0),
+                        );
+                        v = v.normalize();
+                        v *= 10.0;
+
+                        (v, 1.0).into()
+                    };
+                    ParticleInstance {
+                        position,
+                        age,
+                        velocity,
+                        color,
+                        ..Default::default()
+                    }
                 })
-            };
+                .collect();
+
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Instance Buffer"),
+                contents: cast_slice(instances.as_slice()),
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        };
 
-            let bind_group_layout =
-                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Vertex Buffer"),
+            contents: bytes_of(&QUAD_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Index Buffer"),
+            contents: bytes_of(&QUAD_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<ParticleInstance>() as _),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<ParticleUniforms>() as _),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: instance_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: particle_uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let depth_compare = if scene.camera.reverse_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        };
+
+        let render_pipeline = ReloadablePipeline::new(
+            device,
+            vec![include_shader!("particle.wgsl")],
+            move |device, shader_modules| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
                     label: None,
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Storage { read_only: true },
-                                has_dynamic_offset: false,
-                                min_binding_size: wgpu::BufferSize::new(
-                                    size_of::<ParticleInstance>() as _,
-                                ),
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: wgpu::BufferSize::new(
-                                    size_of::<ParticleUniforms>() as _,
-                                ),
-                            },
-                            count: None,
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader_modules[0],
+                        entry_point: "vs_main",
+                        buffers: &[wgpu::VertexBufferLayout {
+                            array_stride: size_of::<Vec3>() as _,
+                            step_mode: wgpu::VertexStepMode::Vertex,
+                            attributes: &[wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 0,
+                                shader_location: 0,
+                            }],
+                        }],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader_modules[0],
+                        entry_point: "fs_main",
+                        targets: &[FrameBuffer::COLOR_FORMAT.into()],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        unclipped_depth: false,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        conservative: false,
+                    },
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: FrameBuffer::DEPTH_FORMAT,
+                        depth_write_enabled: true,
+                        depth_compare,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState {
+                            constant: 0,
+                            slope_scale: 0.0,
+                            clamp: 0.0,
                         },
-                    ],
-                });
+                    }),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
+            },
+        );
 
-            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let simulation_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: None,
-                layout: &bind_group_layout,
                 entries: &[
-                    wgpu::BindGroupEntry {
+                    wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        resource: instance_buffer.as_entire_binding(),
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                size_of::<ParticleInstance>() as _
+                            ),
+                        },
+                        count: None,
                     },
-                    wgpu::BindGroupEntry {
+                    wgpu::BindGroupLayoutEntry {
                         binding: 1,
-                        resource: particle_uniform_buffer.as_entire_binding(),
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                size_of::<SimulationUniforms>() as _,
+                            ),
+                        },
+                        count: None,
                     },
                 ],
             });
 
-            let shader_module = device.create_shader_module(&wgpu::include_wgsl!("particle.wgsl"));
+        let simulation_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &simulation_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: instance_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: simulation_uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
 
+        let simulation_pipeline = {
             let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: None,
-                bind_group_layouts: &[&bind_group_layout],
+                bind_group_layouts: &[&simulation_bind_group_layout],
                 push_constant_ranges: &[],
             });
 
-            let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader_module,
-                    entry_point: "vs_main",
-                    buffers: &[wgpu::VertexBufferLayout {
-                        array_stride: size_of::<Vec3>() as _,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[wgpu::VertexAttribute {
-                            format: wgpu::VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 0,
-                        }],
-                    }],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader_module,
-                    entry_point: "fs_main",
-                    targets: &[FrameBuffers::COLOR_FORMAT.into()],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
+            ReloadablePipeline::new(
+                device,
+                vec![include_shader!("particle_cs.wgsl")],
+                move |device, shader_modules| {
+                    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some("Particle Simulation Compute Pipeline"),
+                        layout: Some(&pipeline_layout),
+                        module: &shader_modules[0],
+                        entry_point: "main",
+                    })
                 },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: FrameBuffers::DEPTH_FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState {
-                        constant: 0,
-                        slope_scale: 0.0,
-                        clamp: 0.0,
-                    },
-                }),
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            });
-
-            let mut encoder =
-                device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
-                    label: None,
-                    color_formats: &[FrameBuffers::COLOR_FORMAT],
-                    depth_stencil: Some(wgpu::RenderBundleDepthStencil {
-                        format: FrameBuffers::DEPTH_FORMAT,
-                        depth_read_only: false,
-                        stencil_read_only: true,
-                    }),
-                    sample_count: 1,
-                    multiview: None,
-                });
-
-            encoder.set_bind_group(0, &bind_group, &[]);
-            encoder.set_pipeline(&render_pipeline);
-            encoder.set_vertex_buffer(0, vertex_buffer.slice(..));
-            encoder.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            encoder.draw_indexed(
-                0..(QUAD_INDICES.len() as _),
-                0,
-                0..scene.particle_system.max_count,
-            );
-
-            encoder.finish(&wgpu::RenderBundleDescriptor {
-                label: Some("Particle Render Bundle"),
-            })
+            )
         };
 
         Self {
             particle_uniform_buffer,
-            particle_render_bundle,
+            simulation_uniform_buffer,
+            vertex_buffer,
+            index_buffer,
+            bind_group,
+            render_pipeline,
+            simulation_bind_group,
+            simulation_pipeline,
+            instance_count: scene.particle_system.max_count,
+            far_depth: scene.camera.far_depth(),
+            simulation_step: 0,
+            lifetime: scene.particle_system.lifetime,
         }
     }
+}
 
-    pub fn update(
-        &self,
+// Simulates the particles and draws them to the color and depth buffers, the pipelines don't
+// depend on their size
+impl Node for ParticleRenderer {
+    fn bind(&mut self, _: &wgpu::Device, _: &Samplers, _: &NodeTextures) {}
+
+    fn update(
+        &mut self,
         device: &wgpu::Device,
         staging_belt: &mut wgpu::util::StagingBelt,
         encoder: &mut wgpu::CommandEncoder,
         scene: &Scene,
+        delta_time: f32,
     ) {
-        let uniforms = ParticleUniforms::new(&scene);
+        let uniforms = ParticleUniforms::new(scene);
 
         staging_belt
             .write_buffer(
@@ -289,13 +410,59 @@ impl ParticleRenderer {
                 device,
             )
             .copy_from_slice(bytes_of(&uniforms));
+
+        // Seeds the respawns, so that particles respawning on different frames differ
+        self.simulation_step = self.simulation_step.wrapping_add(1);
+        // Stretch the ages along with a changed lifetime, so they stay spread over it and the
+        // particles keep respawning evenly instead of all at once
+        let lifetime = scene.particle_system.lifetime;
+        let age_scale = if self.lifetime > 0.0 && lifetime > 0.0 {
+            lifetime / self.lifetime
+        } else {
+            1.0
+        };
+        self.lifetime = lifetime;
+        let simulation_uniforms = SimulationUniforms::new(
+            &scene.particle_system,
+            delta_time,
+            self.simulation_step,
+            age_scale,
+        );
+
+        staging_belt
+            .write_buffer(
+                encoder,
+                &self.simulation_uniform_buffer,
+                0,
+                wgpu::BufferSize::new(size_of::<SimulationUniforms>() as _).unwrap(),
+                device,
+            )
+            .copy_from_slice(bytes_of(&simulation_uniforms));
     }
 
-    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, frame_buffers: &FrameBuffers) {
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+        self.simulation_pipeline.reload(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Particle Simulation Compute Pass"),
+            });
+            compute_pass.set_pipeline(self.simulation_pipeline.get());
+            compute_pass.set_bind_group(0, &self.simulation_bind_group, &[]);
+            compute_pass.dispatch(
+                self.instance_count.div_ceil(SIMULATION_WORKGROUP_SIZE),
+                1,
+                1,
+            );
+        }
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
             color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &frame_buffers.color_texture_view,
+                view: &textures.writes[0].texture_view,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -303,14 +470,18 @@ impl ParticleRenderer {
                 },
             }],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &frame_buffers.depth_texture_view,
+                view: &textures.writes[1].texture_view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
+                    load: wgpu::LoadOp::Clear(self.far_depth),
                     store: true,
                 }),
                 stencil_ops: None,
             }),
         });
-        render_pass.execute_bundles(Some(&self.particle_render_bundle));
+        render_pass.set_pipeline(self.render_pipeline.get());
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..(QUAD_INDICES.len() as _), 0, 0..self.instance_count);
     }
 }
@@ -0,0 +1,52 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+pub struct Recording {
+    output_dir: PathBuf,
+    frame_count: u32,
+    timestep: f32,
+    frame: u32,
+}
+
+impl Recording {
+    pub fn new(output_dir: PathBuf, frame_count: u32, fps: u32) -> Result<Self> {
+        fs::create_dir_all(&output_dir).with_context(|| {
+            format!(
+                "Failed to create recording directory: {}",
+                output_dir.display()
+            )
+        })?;
+
+        Ok(Self {
+            output_dir,
+            frame_count,
+            timestep: 1.0 / fps as f32,
+            frame: 0,
+        })
+    }
+
+    pub fn time(&self) -> f32 {
+        self.frame as f32 * self.timestep
+    }
+
+    pub fn timestep(&self) -> f32 {
+        self.timestep
+    }
+
+    pub fn frame_path(&self) -> PathBuf {
+        self.output_dir.join(format!("frame-{:05}.png", self.frame))
+    }
+
+    pub fn advance(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frame_count
+    }
+}
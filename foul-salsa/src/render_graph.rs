@@ -0,0 +1,101 @@
+use common::render_graph;
+
+use crate::{entity::Scene, frame_buffer::FrameBuffer, samplers::Samplers};
+
+pub use common::render_graph::{TextureDescriptor, TextureId};
+
+// Frame buffers of a pass in the order it declared them
+pub type NodeTextures<'a> = render_graph::NodeTextures<'a, FrameBuffer>;
+
+pub trait Node {
+    // Called when the frame buffers were recreated, e.g. after a resize
+    fn bind(&mut self, device: &wgpu::Device, samplers: &Samplers, textures: &NodeTextures);
+
+    fn update(
+        &mut self,
+        _device: &wgpu::Device,
+        _staging_belt: &mut wgpu::util::StagingBelt,
+        _encoder: &mut wgpu::CommandEncoder,
+        _scene: &Scene,
+        _delta_time: f32,
+    ) {
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device);
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures);
+}
+
+type CreateNode = Box<dyn FnOnce(&wgpu::Device, &Samplers, &NodeTextures) -> Box<dyn Node>>;
+
+// The shared render graph with frame buffers for the textures. Compile it again after a resize to
+// recreate the frame buffers and rebind the nodes.
+#[derive(Default)]
+pub struct RenderGraph(render_graph::RenderGraph<FrameBuffer, Box<dyn Node>, CreateNode>);
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_texture(&mut self, descriptor: TextureDescriptor) -> TextureId {
+        self.0.add_texture(descriptor)
+    }
+
+    pub fn retain(&mut self, texture: TextureId) {
+        self.0.retain(texture);
+    }
+
+    pub fn add_pass<F>(
+        &mut self,
+        name: impl Into<String>,
+        reads: &[TextureId],
+        writes: &[TextureId],
+        create: F,
+    ) where
+        F: FnOnce(&wgpu::Device, &Samplers, &NodeTextures) -> Box<dyn Node> + 'static,
+    {
+        self.0.add_pass(name, reads, writes, Box::new(create));
+    }
+
+    pub fn compile(&mut self, device: &wgpu::Device, samplers: &Samplers, width: u32, height: u32) {
+        self.0.compile(
+            width,
+            height,
+            |descriptor, width, height| {
+                FrameBuffer::new(device, &descriptor.label, width, height, descriptor.format)
+            },
+            |node, textures| node.bind(device, samplers, textures),
+            |create, textures| create(device, samplers, textures),
+        );
+    }
+
+    pub fn frame_buffer(&self, texture: TextureId) -> &FrameBuffer {
+        self.0.target(texture)
+    }
+
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &Scene,
+        delta_time: f32,
+    ) {
+        for node in self.0.nodes_mut() {
+            node.update(device, staging_belt, encoder, scene, delta_time);
+        }
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        for node in self.0.nodes_mut() {
+            node.reload_shaders(device);
+        }
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder) {
+        for (node, textures) in self.0.scheduled() {
+            node.run(encoder, &textures);
+        }
+    }
+}
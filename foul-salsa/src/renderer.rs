@@ -1,11 +1,19 @@
 use std::future::Future;
 
-use anyhow::{Context, Ok, Result};
+use anyhow::{bail, Context, Ok, Result};
+use common::luminance_meter::LuminanceMeter;
+use log::warn;
 use winit::{dpi::PhysicalSize, window::Window};
 
 use crate::{
-    bloom_pass::BloomRenderer, composite_pass::CompositeRenderer, entity::Scene,
-    frame_buffers::FrameBuffers, particle_pass::ParticleRenderer, samplers::Samplers,
+    bloom_pass,
+    capture::{self, Capture, CaptureSource},
+    composite_pass::CompositeRenderer,
+    entity::Scene,
+    frame_buffer::FrameBuffer,
+    particle_pass::ParticleRenderer,
+    render_graph::{RenderGraph, TextureDescriptor, TextureId},
+    samplers::Samplers,
     surface::Surface,
 };
 
@@ -14,11 +22,15 @@ pub struct Renderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
     staging_belt: wgpu::util::StagingBelt,
-    frame_buffers: FrameBuffers,
+    width: u32,
+    height: u32,
     samplers: Samplers,
-    particle_renderer: ParticleRenderer,
-    bloom_renderer: BloomRenderer,
+    render_graph: RenderGraph,
+    color: TextureId,
+    bloom: TextureId,
     composite_renderer: CompositeRenderer,
+    luminance_meter: LuminanceMeter,
+    minimized: bool,
 }
 
 impl Renderer {
@@ -52,7 +64,7 @@ impl Renderer {
 
         let staging_belt = wgpu::util::StagingBelt::new(
             ParticleRenderer::STAGING_BUFFER_CHUNK_SIZE
-                + BloomRenderer::STAGING_BUFFER_CHUNK_SIZE
+                + bloom_pass::STAGING_BUFFER_CHUNK_SIZE
                 + CompositeRenderer::STAGING_BUFFER_CHUNK_SIZE,
         );
 
@@ -61,74 +73,205 @@ impl Renderer {
         let surface = Surface::new(surface, surface_format);
         surface.configure(&device, width, height);
 
-        let frame_buffers = FrameBuffers::new(&device, width, height);
-
         let samplers = Samplers::new(&device);
 
+        // Color and bloom are also read by the composite pass, which runs again for captures
+        let mut render_graph = RenderGraph::new();
+        let color = render_graph.add_texture(TextureDescriptor {
+            label: "Offscreen Color Texture".to_string(),
+            format: FrameBuffer::COLOR_FORMAT,
+            divisor: 1,
+        });
+        let depth = render_graph.add_texture(TextureDescriptor {
+            label: "Depth Texture".to_string(),
+            format: FrameBuffer::DEPTH_FORMAT,
+            divisor: 1,
+        });
+        render_graph.retain(color);
         let particle_renderer = ParticleRenderer::new(&device, scene);
-        let bloom_renderer = BloomRenderer::new(&device, &frame_buffers, &samplers);
-        let composite_renderer =
-            CompositeRenderer::new(&device, &samplers, &frame_buffers, &surface);
+        render_graph.add_pass("Particles", &[], &[color, depth], move |_, _, _| {
+            Box::new(particle_renderer)
+        });
+        let bloom = bloom_pass::add_bloom(&mut render_graph, color);
+        render_graph.retain(bloom);
+        render_graph.compile(&device, &samplers, width, height);
+
+        let composite_renderer = CompositeRenderer::new(
+            &device,
+            &samplers,
+            render_graph.frame_buffer(color),
+            render_graph.frame_buffer(bloom),
+            &surface,
+        );
+        let luminance_meter =
+            LuminanceMeter::new(&device, &render_graph.frame_buffer(color).texture_view);
 
         Ok(Self {
             surface,
             device,
             queue,
             staging_belt,
-            frame_buffers,
+            width,
+            height,
             samplers,
-            particle_renderer,
-            bloom_renderer,
+            render_graph,
+            color,
+            bloom,
             composite_renderer,
+            luminance_meter,
+            minimized: false,
         })
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
+        // A minimized window reports a zero size, rendering is skipped until it's restored
+        self.minimized = width == 0 || height == 0;
+        if self.minimized {
+            return;
+        }
+
         self.surface.configure(&self.device, width, height);
 
-        self.frame_buffers.resize(&self.device, width, height);
+        self.width = width;
+        self.height = height;
+        self.render_graph
+            .compile(&self.device, &self.samplers, width, height);
 
-        self.bloom_renderer
-            .recreate_bind_group(&self.device, &self.frame_buffers, &self.samplers);
         self.composite_renderer.recreate_bind_group(
             &self.device,
-            &self.frame_buffers,
+            self.render_graph.frame_buffer(self.color),
+            self.render_graph.frame_buffer(self.bloom),
             &self.samplers,
         );
+        self.luminance_meter.recreate_bind_group(
+            &self.device,
+            &self.render_graph.frame_buffer(self.color).texture_view,
+        );
     }
 
-    pub fn render(&mut self, scene: &Scene) -> impl Future<Output = ()> {
-        let surface_texture = self
-            .surface
-            .wgpu_surface
-            .get_current_texture()
-            .expect("Failed to get next surface texture");
+    pub fn reload_shaders(&mut self) {
+        self.render_graph.reload_shaders(&self.device);
+        self.composite_renderer.reload_shaders(&self.device);
+        self.luminance_meter.reload_shaders(&self.device);
+    }
+
+    pub fn average_log_luminance(&self) -> Option<f32> {
+        self.luminance_meter.average_log_luminance()
+    }
+
+    pub fn render(&mut self, scene: &Scene, delta_time: f32) -> Result<impl Future<Output = ()>> {
+        if self.minimized {
+            return Ok(self.staging_belt.recall());
+        }
+
+        self.luminance_meter.poll_readback(&self.device);
+
+        let surface_texture = match self.surface.wgpu_surface.get_current_texture() {
+            Result::Ok(surface_texture) => surface_texture,
+            Err(err @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                warn!("Surface {:?}, reconfiguring and skipping frame", err);
+                self.surface
+                    .configure(&self.device, self.width, self.height);
+                return Ok(self.staging_belt.recall());
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                warn!("Timed out getting next surface texture, skipping frame");
+                return Ok(self.staging_belt.recall());
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                bail!("Out of memory while getting next surface texture")
+            }
+        };
 
         let surface_texture_view = surface_texture.texture.create_view(&Default::default());
 
         let mut encoder = self.device.create_command_encoder(&Default::default());
 
-        self.particle_renderer
-            .update(&self.device, &mut self.staging_belt, &mut encoder, scene);
-        self.bloom_renderer
-            .update(&self.device, &mut self.staging_belt, &mut encoder, scene);
+        self.render_graph.update(
+            &self.device,
+            &mut self.staging_belt,
+            &mut encoder,
+            scene,
+            delta_time,
+        );
         self.composite_renderer
             .update(&self.device, &mut self.staging_belt, &mut encoder, scene);
 
         self.staging_belt.finish();
 
-        self.particle_renderer
-            .draw(&mut encoder, &self.frame_buffers);
+        self.render_graph.run(&mut encoder);
 
-        self.bloom_renderer.draw(&mut encoder, &self.frame_buffers);
+        if scene.camera.auto_exposure.is_some() {
+            self.luminance_meter.measure(&mut encoder);
+        }
 
         self.composite_renderer
             .draw(&mut encoder, &surface_texture_view);
 
         self.queue.submit(Some(encoder.finish()));
+        self.luminance_meter.start_readback();
 
         surface_texture.present();
 
-        self.staging_belt.recall()
+        Ok(self.staging_belt.recall())
+    }
+
+    pub fn capture_frame(&self, source: CaptureSource) -> Result<Capture> {
+        let width = self.width;
+        let height = self.height;
+        // There's no frame to read back at a zero size, textures can't have a zero extent
+        if self.minimized || width == 0 || height == 0 {
+            bail!("Can't capture a frame while the window is minimized");
+        }
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+
+        match source {
+            CaptureSource::Surface => {
+                // The surface texture can't be copied from, so compose the last frame again
+                let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Capture Texture"),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.surface.texture_format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                });
+                let texture_view = texture.create_view(&Default::default());
+
+                self.composite_renderer.draw(&mut encoder, &texture_view);
+
+                let data = capture::read_texture(
+                    &self.device,
+                    &self.queue,
+                    encoder,
+                    &texture,
+                    self.surface.texture_format,
+                    width,
+                    height,
+                )?;
+
+                Capture::ldr(width, height, self.surface.texture_format, data)
+            }
+            CaptureSource::HdrColor => {
+                let color = self.render_graph.frame_buffer(self.color);
+                let data = capture::read_texture(
+                    &self.device,
+                    &self.queue,
+                    encoder,
+                    &color.texture,
+                    color.format,
+                    color.width,
+                    color.height,
+                )?;
+
+                Capture::hdr(color.width, color.height, color.format, data)
+            }
+        }
     }
 }
@@ -0,0 +1,171 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::{
+    animation::Animation,
+    entity::{Entity, Scene, Tonemapper},
+};
+
+const DEFAULT_SCENE: &str = include_str!("../scenes/default.ron");
+
+pub fn load(path: &Path) -> Result<Scene> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read scene file: {}", path.display()))?;
+
+    let scene = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("ron") => ron::from_str(&source).map_err(anyhow::Error::from),
+        Some("json") => serde_json::from_str(&source).map_err(anyhow::Error::from),
+        _ => bail!(
+            "Unsupported scene file extension, expected .ron or .json: {}",
+            path.display()
+        ),
+    }
+    .with_context(|| format!("Failed to parse scene file: {}", path.display()))?;
+
+    validate(&scene).with_context(|| format!("Invalid scene file: {}", path.display()))?;
+
+    Ok(scene)
+}
+
+pub fn default_scene() -> Scene {
+    ron::from_str(DEFAULT_SCENE).expect("Failed to parse default scene")
+}
+
+fn validate(scene: &Scene) -> Result<()> {
+    let camera = &scene.camera;
+    ensure!(
+        camera.fov > 0.0 && camera.fov < 180.0,
+        "camera.fov must be between 0 and 180 degrees"
+    );
+    ensure!(
+        camera.near > 0.0 && (camera.infinite_far || camera.near < camera.far),
+        "camera.near must be greater than 0 and less than far"
+    );
+    if let Tonemapper::ExtendedReinhard { white_point } = camera.tonemapper {
+        ensure!(
+            white_point > 0.0,
+            "camera.tonemapper white_point must be greater than 0"
+        );
+    }
+    if let Some(auto_exposure) = &camera.auto_exposure {
+        auto_exposure
+            .validate()
+            .context("Invalid camera.auto_exposure")?;
+    }
+    if let Some(path) = &camera.path {
+        path.validate().context("Invalid camera.path")?;
+    }
+
+    // Without a loop, following parents reaches a root within as many steps as there are entities
+    for entity in Entity::ALL {
+        let mut ancestor = scene.parent(entity);
+        for _ in Entity::ALL {
+            if let Some(parent) = ancestor {
+                ensure!(
+                    parent != entity,
+                    "{:?} must not be parented to itself or its descendants",
+                    entity
+                );
+                ancestor = scene.parent(parent);
+            }
+        }
+    }
+
+    let particle_system = &scene.particle_system;
+    ensure!(
+        particle_system.max_count > 0,
+        "particle_system.max_count must be greater than 0"
+    );
+    ensure!(
+        particle_system.min_speed <= particle_system.max_speed,
+        "particle_system.min_speed must not be greater than max_speed"
+    );
+
+    ensure!(
+        scene.bloom_effect.knee >= 0.0,
+        "bloom_effect.knee must not be negative"
+    );
+
+    for (i, animation) in scene.animations.iter().enumerate() {
+        validate_animation(animation, scene)
+            .with_context(|| format!("Invalid animations[{}]", i))?;
+    }
+
+    Ok(())
+}
+
+fn validate_animation(animation: &Animation, scene: &Scene) -> Result<()> {
+    match animation {
+        Animation::ParticlePosition(curve)
+        | Animation::ParticleRotation(curve)
+        | Animation::ParticleScale(curve) => curve.validate(),
+        Animation::ParticleSize(curve) | Animation::BloomThreshold(curve) => {
+            curve.validate()?;
+            ensure!(
+                curve.keyframes.iter().all(|keyframe| keyframe.value >= 0.0),
+                "Values must not be negative"
+            );
+            Ok(())
+        }
+        Animation::EmissionRate(curve) => {
+            curve.validate()?;
+            ensure!(
+                curve.keyframes.iter().all(|keyframe| keyframe.value > 0.0),
+                "EmissionRate values must be greater than 0"
+            );
+            Ok(())
+        }
+        Animation::Exposure(curve) => {
+            curve.validate()?;
+            ensure!(
+                curve.keyframes.iter().all(|keyframe| keyframe.value > 0.0),
+                "Exposure values must be greater than 0"
+            );
+            ensure!(
+                scene.camera.auto_exposure.is_none(),
+                "Exposure can't be animated while camera.auto_exposure is set"
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_scenes_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(err) = load(&path) {
+                panic!("{:?}", err);
+            }
+        }
+    }
+
+    #[test]
+    fn parent_loops_are_invalid() {
+        let mut scene = default_scene();
+        scene.camera.parent = Some(Entity::ParticleSystem);
+        assert!(validate(&scene).is_ok());
+
+        scene.particle_system.parent = Some(Entity::Camera);
+        assert!(validate(&scene).is_err());
+
+        scene.camera.parent = None;
+        scene.particle_system.parent = Some(Entity::ParticleSystem);
+        assert!(validate(&scene).is_err());
+    }
+
+    #[test]
+    fn animations_default_to_none() {
+        let mut value = serde_json::to_value(default_scene()).unwrap();
+        value.as_object_mut().unwrap().remove("animations");
+
+        let scene: Scene = serde_json::from_value(value).unwrap();
+        assert!(scene.animations.is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+use common::scene_graph::{NodeId, SceneGraph};
+
+use crate::entity::{Entity, Scene, Transform};
+
+// The scene's entities as nodes, kept across frames so that only what moved is recomputed
+pub struct EntityGraph {
+    graph: SceneGraph,
+    camera: NodeId,
+    particle_system: NodeId,
+}
+
+impl EntityGraph {
+    pub fn new() -> Self {
+        let mut graph = SceneGraph::new();
+        let camera = graph.add(Transform::default());
+        let particle_system = graph.add(Transform::default());
+        Self {
+            graph,
+            camera,
+            particle_system,
+        }
+    }
+
+    fn node(&self, entity: Entity) -> NodeId {
+        match entity {
+            Entity::Camera => self.camera,
+            Entity::ParticleSystem => self.particle_system,
+        }
+    }
+
+    // Sets the world matrices of the scene's entities from their transforms and parents
+    pub fn update(&mut self, scene: &mut Scene) {
+        let parents = Entity::ALL.map(|entity| {
+            let parent = scene.parent(entity).map(|parent| self.node(parent));
+            (self.node(entity), parent)
+        });
+        if parents
+            .iter()
+            .any(|&(node, parent)| self.graph.parent(node) != parent)
+        {
+            // Detach everything first, so that swapping parents never forms a cycle midway
+            for (node, _) in parents {
+                self.graph.set_parent(node, None);
+            }
+            for (node, parent) in parents {
+                self.graph.set_parent(node, parent);
+            }
+        }
+
+        self.graph.set_local(self.camera, scene.camera.transform);
+        self.graph
+            .set_local(self.particle_system, scene.particle_system.transform);
+        self.graph.update();
+        scene.camera.world_matrix = self.graph.world_matrix(self.camera);
+        scene.particle_system.world_matrix = self.graph.world_matrix(self.particle_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::test_util::{assert_position, translation};
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn camera_follows_parent_particle_system() {
+        let mut scene = Scene::default();
+        scene.camera.transform = translation(vec3(0.0, 1.0, -5.0));
+        scene.camera.parent = Some(Entity::ParticleSystem);
+        scene.particle_system.transform = translation(vec3(3.0, 0.0, 10.0));
+        let mut graph = EntityGraph::new();
+        graph.update(&mut scene);
+        assert_position(scene.camera.world_matrix, vec3(3.0, 1.0, 5.0));
+
+        scene.particle_system.transform.position = vec3(-3.0, 0.0, 10.0);
+        graph.update(&mut scene);
+        assert_position(scene.camera.world_matrix, vec3(-3.0, 1.0, 5.0));
+        let particle = scene
+            .camera
+            .view_matrix()
+            .transform_point3(vec3(-3.0, 0.0, 10.0));
+        assert!(
+            particle.abs_diff_eq(vec3(0.0, -1.0, 5.0), 1e-5),
+            "{}",
+            particle
+        );
+
+        // Swapping who follows whom
+        scene.camera.parent = None;
+        scene.particle_system.parent = Some(Entity::Camera);
+        graph.update(&mut scene);
+        assert_position(scene.particle_system.world_matrix, vec3(-3.0, 1.0, 5.0));
+    }
+}
@@ -0,0 +1,115 @@
+use std::mem;
+
+use common::animation::Curve;
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{self, Effect},
+    entity::Scene,
+};
+
+// A property of the scene driven by a curve, which overrides the value from the scene file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Animation {
+    ParticlePosition(Curve<Vec3>),
+    // Euler angles in degrees like in the transform. The angles are interpolated, not the
+    // rotation, so a curve can spin more than half a turn between keyframes.
+    ParticleRotation(Curve<Vec3>),
+    ParticleScale(Curve<Vec3>),
+    ParticleSize(Curve<f32>),
+    // Particles respawned per second, which sets the lifetime of the fixed number of particles.
+    // The renderer rescales the ages when the lifetime changes, so the rate changes smoothly.
+    EmissionRate(Curve<f32>),
+    Exposure(Curve<f32>),
+    // Of every bloom effect
+    BloomThreshold(Curve<f32>),
+}
+
+impl Animation {
+    pub fn apply(&self, scene: &mut Scene, time: f32) {
+        match self {
+            Self::ParticlePosition(curve) => scene.particle.transform.position = curve.sample(time),
+            Self::ParticleRotation(curve) => {
+                scene.particle.transform.rotation =
+                    component::rotation_from_euler_degrees(curve.sample(time))
+            }
+            Self::ParticleScale(curve) => scene.particle.transform.scale = curve.sample(time),
+            Self::ParticleSize(curve) => scene.particle.particle.particle_size = curve.sample(time),
+            Self::EmissionRate(curve) => {
+                let particle = &mut scene.particle.particle;
+                particle.lifetime = particle.max_count as f32 / curve.sample(time);
+            }
+            Self::Exposure(curve) => scene.camera.camera.exposure = curve.sample(time),
+            Self::BloomThreshold(curve) => {
+                let threshold = curve.sample(time);
+                for effect in &mut scene.post_processing.effects {
+                    if let Effect::Bloom(bloom) = &mut effect.effect {
+                        bloom.threshold = threshold;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Applies scene.animations in order at time, in seconds
+pub fn animate(scene: &mut Scene, time: f32) {
+    let animations = mem::take(&mut scene.animations);
+    for animation in &animations {
+        animation.apply(scene, time);
+    }
+    scene.animations = animations;
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        animation::{Easing, Keyframe, LoopMode},
+        test_util::assert_approx_eq,
+    };
+    use glam::vec3;
+
+    use super::*;
+
+    fn constant<T>(value: T) -> Curve<T> {
+        Curve {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value,
+                easing: Easing::Linear,
+            }],
+            loop_mode: LoopMode::Once,
+        }
+    }
+
+    #[test]
+    fn animate_applies_animations_to_scene() {
+        let mut scene = Scene {
+            post_processing: crate::entity::PostProcessing {
+                effects: vec![component::PostProcessEffect {
+                    enabled: true,
+                    effect: Effect::Bloom(Default::default()),
+                }],
+            },
+            ..Default::default()
+        };
+        scene.particle.particle.max_count = 100;
+        scene.animations = vec![
+            Animation::ParticleRotation(constant(vec3(0.0, 90.0, 0.0))),
+            Animation::EmissionRate(constant(50.0)),
+            Animation::BloomThreshold(constant(2.0)),
+        ];
+
+        animate(&mut scene, 1.0);
+
+        let forward = scene.particle.transform.rotation * Vec3::Z;
+        assert!(forward.abs_diff_eq(Vec3::X, 1e-5), "{}", forward);
+        assert_approx_eq(scene.particle.particle.lifetime, 2.0);
+        match &scene.post_processing.effects[0].effect {
+            Effect::Bloom(bloom) => assert_approx_eq(bloom.threshold, 2.0),
+            effect => panic!("{:?}", effect),
+        }
+        assert_eq!(scene.animations.len(), 3);
+    }
+}
@@ -1,12 +1,13 @@
 use std::{
-    f32::consts::{PI, TAU},
-    time::Instant,
+    path::PathBuf,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Ok, Result};
-use glam::{vec3, EulerRot, Quat, Vec3};
-use log::{debug, info};
-use pollster::FutureExt;
+use common::{camera_controller::CameraController, shader};
+use glam::{Mat4, Vec3};
+use log::{error, info, warn};
+use pollster::FutureExt as _;
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event::{MouseScrollDelta, VirtualKeyCode},
@@ -14,74 +15,60 @@ use winit::{
 };
 
 use crate::{
-    component,
-    entity::{Camera, Particle, PostProcessing, Scene},
+    animation, component,
+    entity::Scene,
     renderer::Renderer,
+    scene_file,
+    scene_graph::EntityGraph,
+    window::{HasSize, Size},
 };
 
+const SHADER_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct App {
     window: Window,
     scene: Scene,
     renderer: Renderer,
+    scene_watcher: Option<scene_file::Watcher>,
+    file_scene: Scene,
+    shaders_checked_at: Instant,
     new_at: Instant,
+    rendered_at: Instant,
     cursor_locked: bool,
+    camera_controller: CameraController,
+    entity_graph: EntityGraph,
 }
 
 impl App {
-    pub async fn new(window: Window) -> Result<Self> {
+    pub async fn new(
+        window: Window,
+        mut scene: Scene,
+        scene_path: Option<PathBuf>,
+    ) -> Result<Self> {
         let new_at = Instant::now();
 
-        let scene = Scene {
-            camera: {
-                let inner_size = window.inner_size();
-                let aspect_ratio = inner_size.width as f32 / inner_size.height as f32;
-                Camera {
-                    transform: component::Transform {
-                        position: Vec3::ZERO,
-                        rotation: Quat::IDENTITY,
-                        ..Default::default()
-                    },
-                    camera: component::Camera {
-                        fov: 60.,
-                        aspect_ratio,
-                        near: 0.1,
-                        far: 1000.,
-                        exposure: 1.0,
-                    },
-                }
-            },
-            particle: Particle {
-                transform: component::Transform {
-                    position: vec3(0., 0., 10.),
-                    rotation: Quat::from_axis_angle(Vec3::X, PI * -0.25),
-                    scale: Vec3::ONE * 1.5,
-                },
-                particle: component::Particle {
-                    max_count: 1000,
-                    particle_size: 0.01,
-                    position_range: (Vec3::ONE * -0.5, Vec3::ONE * 0.5),
-                    color_range: (Vec3::ONE * 5.0, Vec3::ONE * 10.0),
-                },
-            },
-            post_processing: PostProcessing {
-                bloom: component::Bloom {
-                    threshold: 1.0,
-                    intensity: 1.0,
-                    iterations: 6,
-                    scatter: 0.5,
-                },
-            },
-        };
+        let Size { width, height } = window.size();
+        scene.camera.camera.aspect_ratio = width as f32 / height as f32;
         info!("{:#?}", &scene);
 
+        let mut entity_graph = EntityGraph::new();
+        entity_graph.update(&mut scene);
+
         let renderer = Renderer::new(&window, &scene).await?;
+        let file_scene = scene.clone();
 
         Ok(Self {
             window,
             scene,
             renderer,
+            scene_watcher: scene_path.map(scene_file::Watcher::new),
+            file_scene,
+            shaders_checked_at: new_at,
             new_at,
+            rendered_at: new_at,
             cursor_locked: false,
+            camera_controller: CameraController::new(),
+            entity_graph,
         })
     }
 
@@ -92,7 +79,10 @@ impl App {
             return;
         }
 
-        self.renderer = Renderer::new(&self.window, &self.scene).block_on().unwrap();
+        if size.width > 0 && size.height > 0 {
+            self.scene.camera.camera.aspect_ratio = size.width as f32 / size.height as f32;
+        }
+        self.renderer.resize(size.into());
     }
 
     pub fn on_mouse_up(&mut self) {
@@ -101,26 +91,30 @@ impl App {
         self.cursor_locked = true;
     }
 
+    pub fn on_key_down(&mut self, keycode: VirtualKeyCode) {
+        if self.cursor_locked {
+            self.camera_controller.on_key(keycode, true);
+        }
+    }
+
     pub fn on_key_up(&mut self, keycode: VirtualKeyCode) {
+        self.camera_controller.on_key(keycode, false);
         match keycode {
             VirtualKeyCode::Escape => {
                 self.window.set_cursor_grab(false).unwrap();
                 self.window.set_cursor_visible(true);
                 self.cursor_locked = false;
+                self.camera_controller.release_keys();
             }
-            VirtualKeyCode::K => {
-                self.scene.camera.camera.exposure += 0.1;
-                info!(
-                    "Camera exposure increased: {}",
-                    self.scene.camera.camera.exposure
-                );
+            VirtualKeyCode::O => {
+                self.camera_controller.toggle_mode();
+                info!("Camera mode: {:?}", self.camera_controller.mode());
             }
-            VirtualKeyCode::J => {
-                self.scene.camera.camera.exposure -= 0.1;
-                info!(
-                    "Camera exposure decreased: {}",
-                    self.scene.camera.camera.exposure
-                );
+            VirtualKeyCode::K => self.adjust_exposure(0.1),
+            VirtualKeyCode::J => self.adjust_exposure(-0.1),
+            VirtualKeyCode::F8 => {
+                info!("Simulating GPU device loss");
+                self.renderer.simulate_device_lost();
             }
             _ => (),
         }
@@ -131,13 +125,7 @@ impl App {
             return;
         };
 
-        let mut rotation = self.scene.camera.transform.rotation.to_euler(EulerRot::YXZ);
-        rotation.0 += x as f32 * 0.001;
-        rotation.1 = (rotation.1 + y as f32 * 0.001).clamp(PI * -0.5, PI * 0.5);
-        debug!("rotation: {:?}", rotation);
-
-        self.scene.camera.transform.rotation =
-            Quat::from_euler(glam::EulerRot::YXZ, rotation.0, rotation.1, rotation.2);
+        self.camera_controller.on_mouse_move((x, y));
     }
 
     pub fn on_mouse_scroll(&mut self, delta: winit::event::MouseScrollDelta) {
@@ -151,15 +139,127 @@ impl App {
         self.scene.camera.camera.fov = (self.scene.camera.camera.fov + y * -0.1).clamp(30., 120.);
     }
 
-    pub fn render(&mut self) {
-        let now = Instant::now().duration_since(self.new_at).as_millis() as f32 * 0.001;
+    pub fn render(&mut self) -> Result<()> {
+        let rendered_at = Instant::now();
+        let now = rendered_at.duration_since(self.new_at).as_millis() as f32 * 0.001;
+        let delta_time = rendered_at.duration_since(self.rendered_at).as_secs_f32();
+        self.rendered_at = rendered_at;
+
+        if self.renderer.is_device_lost() {
+            warn!("Recreating GPU device from the current scene");
+            self.renderer.recreate_device(&self.scene).block_on()?;
+        }
+
+        self.reload_scene_file();
+        // Checking every shader file for changes is too slow to do every frame
+        if shader::hot_reload() && self.shaders_checked_at.elapsed() >= SHADER_WATCH_INTERVAL {
+            self.shaders_checked_at = Instant::now();
+            self.renderer.reload_shaders();
+        }
+
+        animate(&mut self.scene, now);
+        if self.scene.camera.path.is_none() {
+            // The camera moves in its parent's space, where the orbit target has to be too
+            let parent_world = self
+                .scene
+                .camera
+                .parent
+                .map_or(Mat4::IDENTITY, |parent| self.scene.world_matrix(parent));
+            let target = parent_world.inverse().transform_point3(
+                self.scene
+                    .particle
+                    .world_matrix
+                    .transform_point3(Vec3::ZERO),
+            );
+            self.camera_controller
+                .update(&mut self.scene.camera.transform, target, delta_time);
+        }
+        self.entity_graph.update(&mut self.scene);
+
+        adapt_exposure(
+            &mut self.scene,
+            self.renderer.average_log_luminance(),
+            delta_time,
+        );
 
-        self.scene.particle.transform.rotation *= Quat::from_axis_angle(Vec3::Y, PI * 0.001);
+        self.renderer.render(&self.scene, delta_time)
+    }
 
-        let scale = ((TAU * now * 0.01).cos() + 1.0) * 0.5;
-        let scale = scale * 8.0 + 2.0;
-        self.scene.particle.transform.scale = Vec3::ONE * scale;
+    // Steps exposure compensation in EV while auto exposure drives the exposure
+    fn adjust_exposure(&mut self, delta: f32) {
+        let camera = &mut self.scene.camera.camera;
+        match &mut camera.auto_exposure {
+            Some(auto_exposure) => {
+                auto_exposure.compensation += delta;
+                info!("Exposure compensation: {}", auto_exposure.compensation);
+            }
+            None => {
+                camera.exposure += delta;
+                info!("Camera exposure: {}", camera.exposure);
+            }
+        }
+    }
+
+    fn reload_scene_file(&mut self) {
+        let watcher = match &mut self.scene_watcher {
+            Some(watcher) => watcher,
+            None => return,
+        };
+
+        let mut file_scene = match watcher.poll() {
+            Some(Result::Ok(scene)) => scene,
+            Some(Err(err)) => {
+                error!("Failed to reload scene: {:?}", err);
+                return;
+            }
+            None => return,
+        };
+        info!("Reloaded scene: {}", watcher.path().display());
+
+        // Keep the seed from the command line unless the file sets one
+        if file_scene.particle.particle.seed.is_none() {
+            file_scene.particle.particle.seed = self.scene.particle.particle.seed;
+        }
+
+        // Transforms are also driven by input and animation, so only take them when edited
+        if file_scene.camera.transform != self.file_scene.camera.transform {
+            self.scene.camera.transform = file_scene.camera.transform;
+        }
+        if file_scene.particle.transform != self.file_scene.particle.transform {
+            self.scene.particle.transform = file_scene.particle.transform;
+        }
+        self.scene.camera.parent = file_scene.camera.parent;
+        self.scene.particle.parent = file_scene.particle.parent;
+
+        self.scene.camera.camera = component::Camera {
+            aspect_ratio: self.scene.camera.camera.aspect_ratio,
+            ..file_scene.camera.camera
+        };
+        self.scene.camera.path = file_scene.camera.path.clone();
+        self.scene.particle.particle = file_scene.particle.particle;
+        self.scene.post_processing = file_scene.post_processing.clone();
+        self.scene.animations = file_scene.animations.clone();
+
+        self.file_scene = file_scene;
+    }
+}
+
+pub fn animate(scene: &mut Scene, now: f32) {
+    animation::animate(scene, now);
+
+    if let Some(path) = &scene.camera.path {
+        let keyframe = path.sample(now);
+        scene.camera.transform.position = keyframe.position;
+        scene.camera.transform.rotation = keyframe.rotation;
+        scene.camera.camera.fov = keyframe.fov;
+    }
+}
 
-        self.renderer.render(&self.scene);
+pub fn adapt_exposure(scene: &mut Scene, average_log_luminance: Option<f32>, delta_time: f32) {
+    let camera = &mut scene.camera.camera;
+    if let (Some(auto_exposure), Some(average_log_luminance)) =
+        (camera.auto_exposure, average_log_luminance)
+    {
+        camera.exposure = auto_exposure.adapt(camera.exposure, average_log_luminance, delta_time);
     }
 }
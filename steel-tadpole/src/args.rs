@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use crate::window::Size;
+
+pub struct Args {
+    pub scene: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub hot_reload_shaders: bool,
+    pub record: Option<PathBuf>,
+    pub record_frames: u32,
+    pub record_fps: u32,
+    pub record_size: Size,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            seed: None,
+            hot_reload_shaders: false,
+            record: None,
+            record_frames: 600,
+            record_fps: 60,
+            record_size: Size {
+                width: 1280,
+                height: 720,
+            },
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        let mut args = Self::default();
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--scene" => {
+                    let path = iter.next().context("--scene requires a scene file path")?;
+                    args.scene = Some(path.into());
+                }
+                "--seed" => {
+                    let seed = iter.next().context("--seed requires a number")?;
+                    args.seed = Some(
+                        seed.parse()
+                            .with_context(|| format!("Invalid seed: {}", seed))?,
+                    );
+                }
+                "--hot-reload-shaders" => args.hot_reload_shaders = true,
+                "--record" => {
+                    let dir = iter
+                        .next()
+                        .context("--record requires an output directory")?;
+                    args.record = Some(dir.into());
+                }
+                "--frames" => {
+                    let frames = iter.next().context("--frames requires a frame count")?;
+                    args.record_frames = frames
+                        .parse()
+                        .with_context(|| format!("Invalid frame count: {}", frames))?;
+                }
+                "--fps" => {
+                    let fps = iter.next().context("--fps requires a frame rate")?;
+                    args.record_fps = fps
+                        .parse()
+                        .with_context(|| format!("Invalid frame rate: {}", fps))?;
+                    if args.record_fps == 0 {
+                        bail!("Frame rate must be greater than 0");
+                    }
+                }
+                "--size" => {
+                    let size = iter.next().context("--size requires WIDTHxHEIGHT")?;
+                    args.record_size =
+                        parse_size(&size).with_context(|| format!("Invalid size: {}", size))?;
+                }
+                _ => bail!("Unknown argument: {}", arg),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn parse_size(size: &str) -> Result<Size> {
+    let (width, height) = size.split_once('x').context("Expected WIDTHxHEIGHT")?;
+    let size = Size {
+        width: width.parse()?,
+        height: height.parse()?,
+    };
+    if size.width == 0 || size.height == 0 {
+        bail!("Width and height must be greater than 0");
+    }
+    Ok(size)
+}
@@ -1,33 +1,177 @@
-use glam::{Quat, Vec3};
+use std::path::PathBuf;
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
-pub struct Transform {
-    pub position: Vec3,
-    pub rotation: Quat,
-    pub scale: Vec3,
-}
+use common::projection::Perspective;
+use glam::{vec3, Mat3, Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+
+pub use common::{
+    auto_exposure::AutoExposure,
+    camera_path::CameraPath,
+    projection::FovAxis,
+    tonemap::Tonemapper,
+    transform::{rotation_from_euler_degrees, Transform},
+};
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Camera {
+    // In degrees, along fov_axis
     pub fov: f32,
+    #[serde(default)]
+    pub fov_axis: FovAxis,
+    #[serde(skip)]
     pub aspect_ratio: f32,
     pub near: f32,
     pub far: f32,
+    // Maps near to depth 1 and far to 0, which spreads float depth precision more evenly
+    #[serde(default)]
+    pub reverse_z: bool,
+    // Ignores far and never clips distant geometry
+    #[serde(default)]
+    pub infinite_far: bool,
     pub exposure: f32,
+    #[serde(default)]
+    pub auto_exposure: Option<AutoExposure>,
+}
+
+impl Camera {
+    pub fn perspective(&self) -> Perspective {
+        Perspective {
+            fov: self.fov,
+            fov_axis: self.fov_axis,
+            aspect_ratio: self.aspect_ratio,
+            near: self.near,
+            far: self.far,
+            reverse_z: self.reverse_z,
+            infinite_far: self.infinite_far,
+        }
+    }
+
+    pub fn projection_matrix(&self) -> Mat4 {
+        self.perspective().matrix()
+    }
+
+    // Depth the depth buffer is cleared to
+    pub fn far_depth(&self) -> f32 {
+        self.perspective().far_depth()
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Particle {
     pub max_count: u32,
     pub particle_size: f32,
+    pub lifetime: f32,
+    pub speed_range: (f32, f32),
     pub color_range: (Vec3, Vec3),
     pub position_range: (Vec3, Vec3),
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Bloom {
     pub intensity: f32,
     pub threshold: f32,
+    // 0 is the hard cutoff of scenes from before the knee
+    #[serde(default)]
+    pub knee: f32,
     pub scatter: f32,
     pub iterations: u8,
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vignette {
+    pub intensity: f32,
+    // Fraction of the distance from the corners to the center that is darkened
+    pub smoothness: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    Bloom(Bloom),
+    Tonemap(Tonemapper),
+    ColorGrading(ColorGrading),
+    Vignette(Vignette),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostProcessEffect {
+    pub enabled: bool,
+    pub effect: Effect,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorGrading {
+    pub temperature: f32,
+    pub tint: f32,
+    pub contrast: f32,
+    pub lift: Vec3,
+    pub gamma: Vec3,
+    pub gain: Vec3,
+    pub saturation: f32,
+    // .cube file, relative paths are resolved against the scene file
+    pub lut: Option<PathBuf>,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            temperature: 0.0,
+            tint: 0.0,
+            contrast: 1.0,
+            lift: Vec3::ZERO,
+            gamma: Vec3::ONE,
+            gain: Vec3::ONE,
+            saturation: 1.0,
+            lut: None,
+        }
+    }
+}
+
+impl ColorGrading {
+    // Von Kries adaptation in LMS space from the D65 white point to one shifted by temperature and
+    // tint, following Unity's post-processing stack
+    pub fn white_balance(&self) -> Mat3 {
+        let lin_to_lms = Mat3::from_cols_array(&[
+            3.90405e-1, 5.49941e-1, 8.92632e-3, //
+            7.08416e-2, 9.63172e-1, 1.35775e-3, //
+            2.31082e-2, 1.28021e-1, 9.36245e-1,
+        ])
+        .transpose();
+        let lms_to_lin = Mat3::from_cols_array(&[
+            2.85847e+0,
+            -1.62879e+0,
+            -2.48910e-2, //
+            -2.10182e-1,
+            1.15820e+0,
+            3.24281e-4, //
+            -4.18120e-2,
+            -1.18169e-1,
+            1.06867e+0,
+        ])
+        .transpose();
+
+        let t1 = self.temperature * 10.0 / 6.0;
+        let t2 = self.tint * 10.0 / 6.0;
+        let x = 0.31271 - t1 * if t1 < 0.0 { 0.1 } else { 0.05 };
+        let standard_illuminant_y = 2.87 * x - 3.0 * x * x - 0.27509507;
+        let y = standard_illuminant_y + t2 * 0.05;
+
+        let d65 = vec3(0.949237, 1.03542, 1.08728);
+        let white = {
+            let (cx, cy, cz) = (x / y, 1.0, (1.0 - x - y) / y);
+            vec3(
+                0.7328 * cx + 0.4296 * cy - 0.1624 * cz,
+                -0.7036 * cx + 1.6975 * cy + 0.0061 * cz,
+                0.0030 * cx + 0.0136 * cy + 0.9834 * cz,
+            )
+        };
+
+        lms_to_lin * Mat3::from_diagonal(d65 / white) * lin_to_lms
+    }
+}
@@ -1,25 +1,152 @@
-use crate::component;
+use glam::Mat4;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+use crate::{animation::Animation, component};
+
+// Names an entity of the scene, for parenting
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Entity {
+    Camera,
+    Particle,
+}
+
+impl Entity {
+    pub const ALL: [Entity; 2] = [Entity::Camera, Entity::Particle];
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Camera {
+    // Relative to the parent
     pub transform: component::Transform,
+    #[serde(default)]
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    #[serde(skip)]
+    pub world_matrix: Mat4,
     pub camera: component::Camera,
+    // Drives the transform and fov instead of the input while set
+    #[serde(default)]
+    pub path: Option<component::CameraPath>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+impl Camera {
+    // The camera looks along +Z of its world matrix, scale is ignored
+    pub fn view_matrix(&self) -> Mat4 {
+        let (_, rotation, translation) = self.world_matrix.to_scale_rotation_translation();
+        Mat4::from_rotation_translation(rotation, translation).inverse()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Particle {
+    // Relative to the parent
     pub transform: component::Transform,
+    #[serde(default)]
+    pub parent: Option<Entity>,
+    // Computed from the transforms of the entity and its parents by scene_graph
+    #[serde(skip)]
+    pub world_matrix: Mat4,
     pub particle: component::Particle,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PostProcessing {
-    pub bloom: component::Bloom,
+    // Applied in order, each one reading the output of the previous
+    pub effects: Vec<component::PostProcessEffect>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Scene {
     pub camera: Camera,
     pub particle: Particle,
     pub post_processing: PostProcessing,
+    // Applied in order every frame, later ones win when they drive the same property
+    #[serde(default)]
+    pub animations: Vec<Animation>,
+}
+
+impl Scene {
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        match entity {
+            Entity::Camera => self.camera.parent,
+            Entity::Particle => self.particle.parent,
+        }
+    }
+
+    pub fn world_matrix(&self, entity: Entity) -> Mat4 {
+        match entity {
+            Entity::Camera => self.camera.world_matrix,
+            Entity::Particle => self.particle.world_matrix,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::{vec3, EulerRot, Quat, Vec3};
+
+    use super::*;
+
+    fn camera(position: Vec3, rotation: Quat) -> Camera {
+        Camera {
+            world_matrix: Mat4::from_rotation_translation(rotation, position),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn view_of_camera_at_origin_is_identity() {
+        let view = camera(Vec3::ZERO, Quat::IDENTITY).view_matrix();
+        assert!(view.abs_diff_eq(Mat4::IDENTITY, 1e-6), "{}", view);
+    }
+
+    #[test]
+    fn view_moves_camera_to_origin_looking_along_z() {
+        let view = camera(vec3(1.0, 2.0, 3.0), Quat::from_rotation_y(FRAC_PI_2)).view_matrix();
+        let ahead = view.transform_point3(vec3(6.0, 2.0, 3.0));
+        assert!(ahead.abs_diff_eq(vec3(0.0, 0.0, 5.0), 1e-5), "{}", ahead);
+        let origin = view.transform_point3(vec3(1.0, 2.0, 3.0));
+        assert!(origin.abs_diff_eq(Vec3::ZERO, 1e-5), "{}", origin);
+    }
+
+    #[test]
+    fn view_matches_look_at_without_roll() {
+        let position = vec3(-2.0, 0.5, 4.0);
+        let rotation = Quat::from_euler(EulerRot::YXZ, 0.7, -0.4, 0.0);
+        let view = camera(position, rotation).view_matrix();
+        let look_at = Mat4::look_at_lh(position, position + rotation * Vec3::Z, Vec3::Y);
+        assert!(
+            view.abs_diff_eq(look_at, 1e-5),
+            "{} is not {}",
+            view,
+            look_at
+        );
+    }
+
+    #[test]
+    fn view_ignores_scale_inherited_from_parent() {
+        let rotation = Quat::from_rotation_y(0.3);
+        let scaled = Camera {
+            world_matrix: Mat4::from_scale_rotation_translation(
+                Vec3::splat(4.0),
+                rotation,
+                vec3(1.0, 2.0, 3.0),
+            ),
+            ..Default::default()
+        };
+        let view = scaled.view_matrix();
+        let expected = camera(vec3(1.0, 2.0, 3.0), rotation).view_matrix();
+        assert!(
+            view.abs_diff_eq(expected, 1e-5),
+            "{} is not {}",
+            view,
+            expected
+        );
+    }
 }
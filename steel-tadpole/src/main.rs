@@ -1,7 +1,8 @@
 use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use log::debug;
+use common::shader;
+use log::{debug, error};
 use pollster::FutureExt as _;
 use winit::{
     dpi::LogicalSize,
@@ -10,17 +11,46 @@ use winit::{
     window::WindowBuilder,
 };
 
+mod animation;
 mod app;
+mod args;
 mod component;
 mod entity;
+mod recording;
 mod renderer;
+mod scene_file;
+mod scene_graph;
 mod window;
 
 use app::App;
+use args::Args;
+use recording::Recording;
 
 fn main() -> Result<()> {
     env_logger::init();
 
+    let args = Args::parse()?;
+    shader::set_hot_reload(args.hot_reload_shaders);
+
+    let mut scene = match &args.scene {
+        Some(path) => scene_file::load(path)?,
+        None => scene_file::default_scene(),
+    };
+    if args.seed.is_some() {
+        scene.particle.particle.seed = args.seed;
+    }
+
+    if let Some(dir) = args.record {
+        let recording = Recording::new(
+            dir,
+            args.record_frames,
+            args.record_fps,
+            args.record_size,
+            scene,
+        )?;
+        return recording.run().block_on();
+    }
+
     let event_loop = EventLoop::new();
 
     let window = WindowBuilder::new()
@@ -33,7 +63,7 @@ fn main() -> Result<()> {
 
     let mut last_render_inst = Instant::now();
 
-    let mut app = App::new(window).block_on()?;
+    let mut app = App::new(window, scene, args.scene).block_on()?;
 
     event_loop.run(move |e, _, control_flow| {
         debug!("{:#?}", e);
@@ -50,6 +80,15 @@ fn main() -> Result<()> {
                     button: MouseButton::Left,
                     ..
                 } => app.on_mouse_up(),
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(keycode),
+                            ..
+                        },
+                    ..
+                } => app.on_key_down(keycode),
                 WindowEvent::KeyboardInput {
                     input:
                         KeyboardInput {
@@ -75,7 +114,10 @@ fn main() -> Result<()> {
                     return;
                 }
 
-                app.render();
+                if let Err(err) = app.render() {
+                    error!("Failed to render: {:?}", err);
+                    *control_flow = ControlFlow::Exit;
+                }
 
                 last_render_inst = Instant::now();
             }
@@ -0,0 +1,80 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{ensure, Context, Result};
+use image::ColorType;
+use log::{debug, info};
+
+use crate::{
+    app,
+    entity::Scene,
+    renderer::{Frame, Renderer},
+    scene_graph::EntityGraph,
+    window::Size,
+};
+
+pub struct Recording {
+    output_dir: PathBuf,
+    frame_count: u32,
+    timestep: f32,
+    size: Size,
+    scene: Scene,
+}
+
+impl Recording {
+    pub fn new(
+        output_dir: PathBuf,
+        frame_count: u32,
+        fps: u32,
+        size: Size,
+        scene: Scene,
+    ) -> Result<Self> {
+        fs::create_dir_all(&output_dir).with_context(|| {
+            format!(
+                "Failed to create recording directory: {}",
+                output_dir.display()
+            )
+        })?;
+
+        Ok(Self {
+            output_dir,
+            frame_count,
+            timestep: 1.0 / fps as f32,
+            size,
+            scene,
+        })
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let Size { width, height } = self.size;
+
+        let mut scene = self.scene.clone();
+        scene.camera.camera.aspect_ratio = width as f32 / height as f32;
+
+        let mut renderer = Renderer::new_headless(self.size, &scene, false).await?;
+        let mut entity_graph = EntityGraph::new();
+
+        for frame in 0..self.frame_count {
+            app::animate(&mut scene, frame as f32 * self.timestep);
+            entity_graph.update(&mut scene);
+            app::adapt_exposure(&mut scene, renderer.average_log_luminance(), self.timestep);
+            renderer.render(&scene, self.timestep)?;
+            ensure!(
+                !renderer.is_device_lost(),
+                "GPU device lost while recording"
+            );
+
+            let Frame { size, data } = renderer.read_output().await?;
+            // Waiting makes the exposure of each frame depend only on the frames before it, not on
+            // how fast the readback happens to be
+            renderer.wait_for_luminance();
+            let path = self.output_dir.join(format!("frame-{:05}.png", frame));
+            image::save_buffer(&path, &data, size.width, size.height, ColorType::Rgba8)
+                .with_context(|| format!("Failed to save frame: {}", path.display()))?;
+            debug!("Recorded frame: {}", path.display());
+        }
+
+        info!("Recorded {} frames", self.frame_count);
+
+        Ok(())
+    }
+}
@@ -1,6 +1,609 @@
 mod particle;
 mod postprocessing;
-mod renderer;
+mod render_graph;
+mod render_target;
 mod wgpu_ext;
 
-pub use renderer::Renderer;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use anyhow::{bail, Context, Ok, Result};
+use common::luminance_meter::LuminanceMeter;
+use log::{error, warn};
+
+use crate::{
+    entity::{PostProcessing, Scene},
+    window::{Size, Window},
+};
+
+use self::{
+    particle::ParticleNode,
+    postprocessing::{OutputRenderPass, PostProcessStack},
+    render_graph::{begin_render_pass, RenderGraph, TextureDescriptor, TextureId},
+    render_target::RenderTarget,
+};
+
+const HDR_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const OFFSCREEN_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+enum RenderOutput {
+    Surface(wgpu::Surface),
+    Offscreen(RenderTarget),
+}
+
+pub struct Frame {
+    pub size: Size,
+    pub data: Vec<u8>,
+}
+
+// Where adapters come from, again when the device is lost
+struct AdapterSource {
+    instance: wgpu::Instance,
+    force_fallback_adapter: bool,
+}
+
+impl AdapterSource {
+    async fn request(&self, compatible_surface: Option<&wgpu::Surface>) -> Result<wgpu::Adapter> {
+        self.instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface,
+                force_fallback_adapter: self.force_fallback_adapter,
+            })
+            .await
+            .context("No adapter found")
+    }
+}
+
+pub struct Renderer {
+    adapter_source: AdapterSource,
+    output: RenderOutput,
+    output_format: wgpu::TextureFormat,
+    size: Size,
+    device: wgpu::Device,
+    device_lost: Arc<AtomicBool>,
+    queue: wgpu::Queue,
+    render_graph: RenderGraph,
+    color: TextureId,
+    result: TextureId,
+    // What the render graph was built for, None when it has to be built again
+    post_process_stack: Option<PostProcessStack>,
+    // The effects the stack was last checked against, so it's only built again when they change
+    post_processing: Option<PostProcessing>,
+    output_render_pass: OutputRenderPass,
+    luminance_meter: LuminanceMeter,
+}
+
+impl Renderer {
+    pub async fn new(window: &impl Window, scene: &Scene) -> Result<Self> {
+        let adapter_source = AdapterSource {
+            instance: wgpu::Instance::new(wgpu::Backends::PRIMARY),
+            force_fallback_adapter: false,
+        };
+        let surface = unsafe { adapter_source.instance.create_surface(&window) };
+
+        let adapter = adapter_source.request(Some(&surface)).await?;
+
+        let surface_format = surface
+            .get_preferred_format(&adapter)
+            .context("No preferred format found")?;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await?;
+
+        let size = window.size();
+
+        configure_surface(&surface, &device, surface_format, size.width, size.height);
+
+        Ok(Self::with_output(
+            adapter_source,
+            device,
+            queue,
+            RenderOutput::Surface(surface),
+            surface_format,
+            size,
+            scene,
+        ))
+    }
+
+    pub async fn new_headless(
+        size: Size,
+        scene: &Scene,
+        force_fallback_adapter: bool,
+    ) -> Result<Self> {
+        // WGPU_BACKEND=gl picks e.g. a software OpenGL driver where there's no Vulkan one
+        let backends = wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY);
+        let adapter_source = AdapterSource {
+            instance: wgpu::Instance::new(backends),
+            force_fallback_adapter,
+        };
+
+        let adapter = adapter_source.request(None).await?;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await?;
+
+        let output_render_target = RenderTarget::new(
+            &device,
+            "Offscreen Output Texture",
+            size.width,
+            size.height,
+            OFFSCREEN_TEXTURE_FORMAT,
+        );
+
+        Ok(Self::with_output(
+            adapter_source,
+            device,
+            queue,
+            RenderOutput::Offscreen(output_render_target),
+            OFFSCREEN_TEXTURE_FORMAT,
+            size,
+            scene,
+        ))
+    }
+
+    fn with_output(
+        adapter_source: AdapterSource,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        output: RenderOutput,
+        output_format: wgpu::TextureFormat,
+        size: Size,
+        scene: &Scene,
+    ) -> Self {
+        let device_lost = watch_device_lost(&device);
+
+        let mut render_graph = RenderGraph::new();
+        let (color, result) = build_render_graph(&mut render_graph, &device, &queue, scene, size);
+
+        let output_render_pass = OutputRenderPass::new(
+            &device,
+            render_graph.render_target(result).texture.wgpu_texture(),
+            output_format,
+        );
+
+        let luminance_meter =
+            LuminanceMeter::new(&device, &render_graph.render_target(color).texture_view);
+
+        Self {
+            adapter_source,
+            output,
+            output_format,
+            size,
+            device,
+            device_lost,
+            queue,
+            render_graph,
+            color,
+            result,
+            post_process_stack: Some(PostProcessStack::new(&scene.post_processing)),
+            post_processing: Some(scene.post_processing.clone()),
+            output_render_pass,
+            luminance_meter,
+        }
+    }
+
+    pub fn resize(&mut self, size: Size) {
+        // A minimized window reports a zero size, rendering is skipped until it's restored
+        self.size = size;
+        let Size { width, height } = size;
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        match &mut self.output {
+            RenderOutput::Surface(surface) => {
+                configure_surface(surface, &self.device, self.output_format, width, height);
+            }
+            RenderOutput::Offscreen(render_target) => {
+                *render_target = RenderTarget::new(
+                    &self.device,
+                    "Offscreen Output Texture",
+                    width,
+                    height,
+                    self.output_format,
+                );
+            }
+        }
+
+        // The render graph textures are relative to the output size
+        self.post_process_stack = None;
+        self.post_processing = None;
+    }
+
+    fn rebuild_render_graph(&mut self, scene: &Scene) {
+        let (color, result) = build_render_graph(
+            &mut self.render_graph,
+            &self.device,
+            &self.queue,
+            scene,
+            self.size,
+        );
+        self.color = color;
+        self.result = result;
+        self.post_process_stack = Some(PostProcessStack::new(&scene.post_processing));
+
+        self.output_render_pass.recreate_bind_group(
+            &self.device,
+            self.render_graph
+                .render_target(result)
+                .texture
+                .wgpu_texture(),
+        );
+        self.luminance_meter.recreate_bind_group(
+            &self.device,
+            &self.render_graph.render_target(color).texture_view,
+        );
+    }
+
+    // Replaces the lost device and everything created from it. The surface is kept and configured
+    // for the new device, a window can't have a second surface while the first one is alive.
+    pub async fn recreate_device(&mut self, scene: &Scene) -> Result<()> {
+        let compatible_surface = match &self.output {
+            RenderOutput::Surface(surface) => Some(surface),
+            RenderOutput::Offscreen(_) => None,
+        };
+        let adapter = self.adapter_source.request(compatible_surface).await?;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await?;
+
+        self.device_lost = watch_device_lost(&device);
+        self.device = device;
+        self.queue = queue;
+
+        let Size { width, height } = self.size;
+        match &mut self.output {
+            // A minimized window is configured once it's restored
+            RenderOutput::Surface(surface) => {
+                if width > 0 && height > 0 {
+                    configure_surface(surface, &self.device, self.output_format, width, height);
+                }
+            }
+            RenderOutput::Offscreen(render_target) => {
+                *render_target = RenderTarget::new(
+                    &self.device,
+                    "Offscreen Output Texture",
+                    width,
+                    height,
+                    self.output_format,
+                );
+            }
+        }
+
+        // Nodes aren't reused across devices
+        self.render_graph = RenderGraph::new();
+        let (color, result) = build_render_graph(
+            &mut self.render_graph,
+            &self.device,
+            &self.queue,
+            scene,
+            self.size,
+        );
+        self.color = color;
+        self.result = result;
+        self.post_process_stack = Some(PostProcessStack::new(&scene.post_processing));
+        self.post_processing = Some(scene.post_processing.clone());
+
+        self.output_render_pass = OutputRenderPass::new(
+            &self.device,
+            self.render_graph
+                .render_target(result)
+                .texture
+                .wgpu_texture(),
+            self.output_format,
+        );
+        self.luminance_meter = LuminanceMeter::new(
+            &self.device,
+            &self.render_graph.render_target(color).texture_view,
+        );
+
+        Ok(())
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::SeqCst)
+    }
+
+    pub fn simulate_device_lost(&self) {
+        self.device_lost.store(true, Ordering::SeqCst);
+    }
+
+    pub fn render(&mut self, scene: &Scene, delta_time: f32) -> Result<()> {
+        // Nothing can be drawn with a lost device until the owner calls recreate_device
+        if self.is_device_lost() {
+            return Ok(());
+        }
+
+        let Size { width, height } = self.size;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let (surface_texture, output_texture_view) = match &self.output {
+            RenderOutput::Surface(surface) => {
+                let surface_texture = match surface.get_current_texture() {
+                    Result::Ok(surface_texture) => surface_texture,
+                    Err(err @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                        warn!("Surface {:?}, reconfiguring and skipping frame", err);
+                        configure_surface(surface, &self.device, self.output_format, width, height);
+                        return Ok(());
+                    }
+                    Err(wgpu::SurfaceError::Timeout) => {
+                        warn!("Timed out getting next surface texture, skipping frame");
+                        return Ok(());
+                    }
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        bail!("Out of memory while getting next surface texture")
+                    }
+                };
+                let surface_texture_view = surface_texture.texture.create_view(&Default::default());
+                (Some(surface_texture), surface_texture_view)
+            }
+            RenderOutput::Offscreen(render_target) => (
+                None,
+                render_target
+                    .texture
+                    .wgpu_texture()
+                    .create_view(&Default::default()),
+            ),
+        };
+
+        if self.post_processing.as_ref() != Some(&scene.post_processing) {
+            let post_process_stack = PostProcessStack::new(&scene.post_processing);
+            if self.post_process_stack.as_ref() != Some(&post_process_stack) {
+                self.rebuild_render_graph(scene);
+            }
+            self.post_processing = Some(scene.post_processing.clone());
+        }
+
+        self.luminance_meter.poll_readback(&self.device);
+
+        self.render_graph
+            .update(&self.device, &self.queue, scene, delta_time);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        self.render_graph.run(&mut encoder);
+
+        if scene.camera.camera.auto_exposure.is_some() {
+            self.luminance_meter.measure(&mut encoder);
+        }
+
+        self.output_render_pass.draw(&mut begin_render_pass(
+            &mut encoder,
+            "Output Render Pass",
+            &output_texture_view,
+        ));
+
+        let command_buffer = encoder.finish();
+
+        // Submitting to a lost device panics, so drop the frame if it was lost while encoding
+        if self.is_device_lost() {
+            return Ok(());
+        }
+
+        self.queue.submit(std::iter::once(command_buffer));
+        self.luminance_meter.start_readback();
+
+        if let Some(surface_texture) = surface_texture {
+            surface_texture.present();
+        }
+
+        Ok(())
+    }
+
+    pub fn reload_shaders(&mut self) {
+        self.render_graph.reload_shaders(&self.device);
+        self.output_render_pass.reload_shaders(&self.device);
+        self.luminance_meter.reload_shaders(&self.device);
+    }
+
+    pub fn average_log_luminance(&self) -> Option<f32> {
+        self.luminance_meter.average_log_luminance()
+    }
+
+    pub fn wait_for_luminance(&mut self) {
+        self.luminance_meter.wait_for_readback(&self.device);
+    }
+
+    pub async fn read_output(&self) -> Result<Frame> {
+        let texture = match &self.output {
+            RenderOutput::Offscreen(render_target) => &render_target.texture,
+            RenderOutput::Surface(_) => bail!("Surface output can't be read back"),
+        };
+
+        let data = wgpu_ext::read_texture(&self.device, &self.queue, texture).await?;
+
+        Ok(Frame {
+            size: Size {
+                width: texture.width(),
+                height: texture.height(),
+            },
+            data,
+        })
+    }
+}
+
+// Other uncaptured errors are bugs and panic
+fn watch_device_lost(device: &wgpu::Device) -> Arc<AtomicBool> {
+    let device_lost = Arc::new(AtomicBool::new(false));
+    device.on_uncaptured_error({
+        let device_lost = device_lost.clone();
+        move |err| {
+            if is_device_lost_error(&err) {
+                error!("GPU device lost: {}", err);
+                device_lost.store(true, Ordering::SeqCst);
+            } else {
+                panic!("wgpu error: {}", err);
+            }
+        }
+    });
+    device_lost
+}
+
+// wgpu reports device loss as an ordinary error caused by DeviceError::Lost somewhere in its
+// chain, the same way it finds DeviceError::OutOfMemory
+fn is_device_lost_error(err: &wgpu::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(err) = source {
+        if let Some(wgpu_core::device::DeviceError::Lost) =
+            err.downcast_ref::<wgpu_core::device::DeviceError>()
+        {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+fn configure_surface(
+    surface: &wgpu::Surface,
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) {
+    surface.configure(
+        device,
+        &wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+        },
+    );
+}
+
+// Particles drawn to the color texture followed by the post processing effects. Returns the color
+// texture and the result of the effects, both are kept for use outside of the graph.
+fn build_render_graph(
+    graph: &mut RenderGraph,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    scene: &Scene,
+    size: Size,
+) -> (TextureId, TextureId) {
+    graph.clear();
+
+    let color = graph.add_texture(TextureDescriptor {
+        label: "Color Texture".to_string(),
+        format: HDR_TEXTURE_FORMAT,
+        divisor: 1,
+    });
+    let depth = graph.add_texture(TextureDescriptor {
+        label: "Depth Texture".to_string(),
+        format: DEPTH_TEXTURE_FORMAT,
+        divisor: 1,
+    });
+    // Read by the luminance meter
+    graph.retain(color);
+
+    graph.add_pass("Particles", &[], &[color, depth], {
+        let scene = scene.clone();
+        move |device, _, textures| Box::new(ParticleNode::new(device, textures, &scene))
+    });
+
+    let result = PostProcessStack::new(&scene.post_processing).add_to_graph(graph, color, size);
+    graph.retain(result);
+
+    graph.compile(device, queue, size);
+
+    (color, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use pollster::FutureExt as _;
+    use wgpu_core::device::DeviceError;
+
+    use crate::{scene_file, scene_graph::EntityGraph};
+
+    use super::*;
+
+    // wgpu wraps the errors of its backend in its own context error
+    #[derive(Debug)]
+    struct ContextError(DeviceError);
+
+    impl std::fmt::Display for ContextError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "In a test")
+        }
+    }
+
+    impl std::error::Error for ContextError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    fn validation_error(source: impl std::error::Error + Send + 'static) -> wgpu::Error {
+        wgpu::Error::Validation {
+            source: Box::new(source),
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn device_lost_is_found_in_the_error_chain() {
+        assert!(is_device_lost_error(&validation_error(DeviceError::Lost)));
+        assert!(is_device_lost_error(&validation_error(ContextError(
+            DeviceError::Lost
+        ))));
+    }
+
+    #[test]
+    fn other_errors_are_not_device_lost() {
+        assert!(!is_device_lost_error(&validation_error(
+            DeviceError::Invalid
+        )));
+        assert!(!is_device_lost_error(&validation_error(ContextError(
+            DeviceError::OutOfMemory
+        ))));
+        assert!(!is_device_lost_error(&wgpu::Error::OutOfMemory {
+            source: Box::new(DeviceError::OutOfMemory),
+        }));
+    }
+
+    #[test]
+    fn renders_again_after_recreating_a_lost_device() -> Result<()> {
+        let size = Size {
+            width: 64,
+            height: 48,
+        };
+        let mut scene = scene_file::default_scene();
+        // The particles are placed again on the new device, and have to end up in the same spots
+        scene.particle.particle.seed = Some(1);
+        EntityGraph::new().update(&mut scene);
+
+        let mut renderer = Renderer::new_headless(size, &scene, true).block_on()?;
+        renderer.render(&scene, 0.0)?;
+        let before = renderer.read_output().block_on()?;
+
+        renderer.simulate_device_lost();
+        assert!(renderer.is_device_lost());
+        // Skipped rather than submitted to the lost device
+        renderer.render(&scene, 0.0)?;
+
+        renderer.recreate_device(&scene).block_on()?;
+        assert!(!renderer.is_device_lost());
+        renderer.render(&scene, 0.0)?;
+        let after = renderer.read_output().block_on()?;
+
+        assert_eq!(after.size, before.size);
+        assert!(
+            after.data == before.data,
+            "The frame rendered on the new device differs"
+        );
+        Ok(())
+    }
+}
@@ -1,13 +1,18 @@
 use std::{mem::size_of, time::SystemTime};
 
 use bytemuck::{bytes_of, cast_slice, Pod, Zeroable};
-use glam::{const_vec3, vec3, Mat4, Vec3, Vec4};
+use common::shader::{include_shader, ReloadablePipeline};
+use glam::{const_vec3, vec2, vec3, Mat4, Vec2, Vec3, Vec4};
 use log::info;
 use rand::prelude::*;
 use rand_pcg::Pcg64Mcg;
 use wgpu::util::DeviceExt;
 
-use crate::{component::Particle, entity::Scene};
+use crate::{
+    component::Particle,
+    entity::Scene,
+    renderer::render_graph::{Node, NodeTextures},
+};
 
 const QUAD_VERTICES: [Vec3; 4] = [
     const_vec3!([-0.5, -0.5, 0.]),
@@ -16,6 +21,7 @@ const QUAD_VERTICES: [Vec3; 4] = [
     const_vec3!([0.5, 0.5, 0.]),
 ];
 const QUAD_INDICES: [u16; 6] = [0, 2, 1, 1, 2, 3];
+const SIMULATION_WORKGROUP_SIZE: u32 = 64;
 
 #[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
 #[repr(C)]
@@ -32,41 +38,48 @@ impl Uniforms {
             camera, particle, ..
         } = scene;
 
-        let p_mat = {
-            let fovy = camera.camera.fov / camera.camera.aspect_ratio / 180.;
-            Mat4::perspective_lh(
-                fovy,
-                camera.camera.aspect_ratio,
-                camera.camera.near,
-                camera.camera.far,
-            )
-        };
-
-        let v_mat = {
-            let center = camera.transform.position + camera.transform.rotation * Vec3::Z;
-            let up = Vec3::Y;
-            Mat4::look_at_lh(camera.transform.position, center, up)
-        };
-
-        let m_mat = Mat4::from_scale_rotation_translation(
-            particle.transform.scale,
-            particle.transform.rotation,
-            particle.transform.position,
-        );
-
         Self {
-            mv_mat: v_mat * m_mat,
-            p_mat,
+            mv_mat: camera.view_matrix() * particle.world_matrix,
+            p_mat: camera.camera.projection_matrix(),
             particle_size: particle.particle.particle_size,
             ..Default::default()
         }
     }
 }
 
+#[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
+#[repr(C)]
+struct SimulationUniforms {
+    position_range_min: Vec3,
+    delta_time: f32,
+    position_range_max: Vec3,
+    lifetime: f32,
+    speed_range: Vec2,
+    seed: u32,
+    age_scale: f32,
+}
+
+impl SimulationUniforms {
+    fn new(particle: &Particle, delta_time: f32, seed: u32, age_scale: f32) -> Self {
+        Self {
+            position_range_min: particle.position_range.0,
+            delta_time,
+            position_range_max: particle.position_range.1,
+            lifetime: particle.lifetime,
+            speed_range: vec2(particle.speed_range.0, particle.speed_range.1),
+            seed,
+            age_scale,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default, Pod, Zeroable)]
 #[repr(C)]
 struct Instance {
-    position: Vec4,
+    position: Vec3,
+    age: f32,
+    velocity: Vec3,
+    _pad0: [u8; 4],
     color: Vec4,
 }
 
@@ -80,10 +93,12 @@ impl Instances {
 
 impl Instances {
     fn new(particle: &Particle) -> Self {
-        let rand_seed = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as _;
+        let rand_seed = particle.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as _
+        });
 
         let mut rng = Pcg64Mcg::seed_from_u64(rand_seed);
         info!("Seeded RNG with {}", rand_seed);
@@ -92,12 +107,27 @@ impl Instances {
             .map(|_| {
                 let position = {
                     let position_range = particle.position_range;
-                    let v = vec3(
+                    vec3(
                         rng.gen_range(position_range.0.x..=position_range.1.x),
                         rng.gen_range(position_range.0.y..=position_range.1.y),
                         rng.gen_range(position_range.0.z..=position_range.1.z),
-                    );
-                    (v, 1.0).into()
+                    )
+                };
+                let velocity = {
+                    let speed_range = particle.speed_range;
+                    let direction = vec3(
+                        rng.gen_range(-1.0..=1.0),
+                        rng.gen_range(-1.0..=1.0),
+                        rng.gen_range(-1.0..=1.0),
+                    )
+                    .normalize_or_zero();
+                    direction * rng.gen_range(speed_range.0..=speed_range.1)
+                };
+                // Spread initial ages so that particles don't all respawn on the same frame
+                let age = if particle.lifetime > 0.0 {
+                    rng.gen_range(0.0..particle.lifetime)
+                } else {
+                    0.0
                 };
                 let color = {
                     let color_range = particle.color_range;
@@ -108,7 +138,13 @@ impl Instances {
                     );
                     (v, 1.0).into()
                 };
-                Instance { position, color }
+                Instance {
+                    position,
+                    age,
+                    velocity,
+                    color,
+                    ..Default::default()
+                }
             })
             .collect::<Vec<_>>();
 
@@ -119,12 +155,17 @@ impl Instances {
 pub struct ParticleRenderer {
     particle_cache: Particle,
     uniform_buffer: wgpu::Buffer,
+    simulation_uniform_buffer: wgpu::Buffer,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     instance_buffer: wgpu::Buffer,
     instance_count: u32,
+    reverse_z: bool,
+    simulation_step: u32,
     bind_group: wgpu::BindGroup,
-    render_pipeline: wgpu::RenderPipeline,
+    simulation_bind_group: wgpu::BindGroup,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
+    simulation_pipeline: ReloadablePipeline<wgpu::ComputePipeline>,
 }
 
 impl ParticleRenderer {
@@ -171,58 +212,69 @@ impl ParticleRenderer {
             ],
         });
 
-        let shader_module = device.create_shader_module(&wgpu::include_wgsl!("particle.wgsl"));
-
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[&bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: size_of::<Vec3>() as _,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[wgpu::VertexAttribute {
-                        format: wgpu::VertexFormat::Float32x3,
-                        offset: 0,
-                        shader_location: 0,
-                    }],
-                }],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fs_main",
-                targets: &[color_format.into()],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
+        let reverse_z = scene.camera.camera.reverse_z;
+        let depth_compare = if reverse_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        };
+
+        let render_pipeline = ReloadablePipeline::new(
+            device,
+            vec![include_shader!("particle.wgsl")],
+            move |device, shader_modules| {
+                device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: None,
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader_modules[0],
+                        entry_point: "vs_main",
+                        buffers: &[wgpu::VertexBufferLayout {
+                            array_stride: size_of::<Vec3>() as _,
+                            step_mode: wgpu::VertexStepMode::Vertex,
+                            attributes: &[wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Float32x3,
+                                offset: 0,
+                                shader_location: 0,
+                            }],
+                        }],
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader_modules[0],
+                        entry_point: "fs_main",
+                        targets: &[color_format.into()],
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        unclipped_depth: false,
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        conservative: false,
+                    },
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: depth_format,
+                        depth_write_enabled: true,
+                        depth_compare,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState {
+                            constant: 0,
+                            slope_scale: 0.0,
+                            clamp: 0.0,
+                        },
+                    }),
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                })
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: depth_format,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState {
-                    constant: 0,
-                    slope_scale: 0.0,
-                    clamp: 0.0,
-                },
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
+        );
 
         let particle = &scene.particle.particle;
 
@@ -240,6 +292,13 @@ impl ParticleRenderer {
             mapped_at_creation: false,
         });
 
+        let simulation_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Simulation Uniform Buffer"),
+            size: size_of::<SimulationUniforms>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
             layout: &bind_group_layout,
@@ -255,34 +314,228 @@ impl ParticleRenderer {
             ],
         });
 
+        let simulation_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(size_of::<Instance>() as _),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                size_of::<SimulationUniforms>() as _,
+                            ),
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let simulation_pipeline = {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&simulation_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+            ReloadablePipeline::new(
+                device,
+                vec![include_shader!("simulation.wgsl")],
+                move |device, shader_modules| {
+                    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                        label: Some("Particle Simulation Compute Pipeline"),
+                        layout: Some(&pipeline_layout),
+                        module: &shader_modules[0],
+                        entry_point: "main",
+                    })
+                },
+            )
+        };
+
+        let simulation_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &simulation_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: instance_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: simulation_uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
         Self {
-            particle_cache: particle.clone(),
+            particle_cache: *particle,
             vertex_buffer,
             index_buffer,
             instance_buffer,
             instance_count,
+            reverse_z,
+            simulation_step: 0,
             uniform_buffer: particle_uniform_buffer,
+            simulation_uniform_buffer,
             bind_group,
+            simulation_bind_group,
             render_pipeline,
+            simulation_pipeline,
         }
     }
 
-    pub fn update(&self, queue: &wgpu::Queue, scene: &Scene) {
-        if self.particle_cache != scene.particle.particle {
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    pub fn update(&mut self, queue: &wgpu::Queue, scene: &Scene, delta_time: f32) {
+        let particle = &scene.particle.particle;
+        // Colors are only assigned on creation, so respawn every instance when they change
+        if particle.color_range != self.particle_cache.color_range
+            || particle.seed != self.particle_cache.seed
+        {
             queue.write_buffer(
                 &self.instance_buffer,
                 0,
-                cast_slice(&Instances::new(&scene.particle.particle).as_slice()),
+                cast_slice(Instances::new(particle).as_slice()),
             );
         }
+        // Stretch the ages along with a changed lifetime, so they stay spread over it and the
+        // particles keep respawning evenly instead of all at once
+        let age_scale = if self.particle_cache.lifetime > 0.0 && particle.lifetime > 0.0 {
+            particle.lifetime / self.particle_cache.lifetime
+        } else {
+            1.0
+        };
+        self.particle_cache = *particle;
         queue.write_buffer(&self.uniform_buffer, 0, bytes_of(&Uniforms::new(scene)));
+
+        self.simulation_step = self.simulation_step.wrapping_add(1);
+        queue.write_buffer(
+            &self.simulation_uniform_buffer,
+            0,
+            bytes_of(&SimulationUniforms::new(
+                &scene.particle.particle,
+                delta_time,
+                self.simulation_step,
+                age_scale,
+            )),
+        );
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+        self.simulation_pipeline.reload(device);
+    }
+
+    pub fn simulate<'cpass>(&'cpass self, cpass: &mut wgpu::ComputePass<'cpass>) {
+        cpass.set_pipeline(self.simulation_pipeline.get());
+        cpass.set_bind_group(0, &self.simulation_bind_group, &[]);
+        cpass.dispatch(
+            self.instance_count.div_ceil(SIMULATION_WORKGROUP_SIZE),
+            1,
+            1,
+        );
     }
 
     pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
-        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_pipeline(self.render_pipeline.get());
         rpass.set_bind_group(0, &self.bind_group, &[]);
         rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         rpass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
         rpass.draw_indexed(0..(QUAD_INDICES.len() as _), 0, 0..self.instance_count);
     }
 }
+
+// Simulates the particles and draws them to the color and depth textures it writes
+pub struct ParticleNode {
+    color_format: wgpu::TextureFormat,
+    depth_format: wgpu::TextureFormat,
+    far_depth: f32,
+    particle_renderer: ParticleRenderer,
+}
+
+impl ParticleNode {
+    pub fn new(device: &wgpu::Device, textures: &NodeTextures, scene: &Scene) -> Self {
+        let color_format = textures.writes[0].texture.format();
+        let depth_format = textures.writes[1].texture.format();
+        Self {
+            color_format,
+            depth_format,
+            far_depth: scene.camera.camera.far_depth(),
+            particle_renderer: ParticleRenderer::new(device, color_format, depth_format, scene),
+        }
+    }
+}
+
+impl Node for ParticleNode {
+    fn bind(&mut self, _: &wgpu::Device, _: &NodeTextures) {}
+
+    fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+        delta_time: f32,
+    ) {
+        // The depth test direction is part of the pipeline
+        if self.particle_renderer.instance_count() != scene.particle.particle.max_count
+            || self.particle_renderer.reverse_z() != scene.camera.camera.reverse_z
+        {
+            self.particle_renderer =
+                ParticleRenderer::new(device, self.color_format, self.depth_format, scene);
+        }
+        self.far_depth = scene.camera.camera.far_depth();
+        self.particle_renderer.update(queue, scene, delta_time);
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.particle_renderer.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Particle Simulation Compute Pass"),
+            });
+            self.particle_renderer.simulate(&mut cpass);
+        }
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Particle Render Pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: &textures.writes[0].texture_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &textures.writes[1].texture_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.far_depth),
+                    store: false,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        self.particle_renderer.draw(&mut rpass);
+    }
+}
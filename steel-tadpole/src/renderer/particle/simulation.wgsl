@@ -0,0 +1,64 @@
+struct Uniforms {
+  position_range_min: vec3<f32>,
+  delta_time: f32,
+  position_range_max: vec3<f32>,
+  lifetime: f32,
+  speed_range: vec2<f32>,
+  seed: u32,
+  age_scale: f32,
+}
+
+struct Instance {
+  position: vec3<f32>,
+  age: f32,
+  velocity: vec3<f32>,
+  color: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> instances: array<Instance>;
+@group(0) @binding(1)
+var<uniform> uniforms: Uniforms;
+
+// PCG hash, see https://www.jcgt.org/published/0009/03/02/
+fn hash(value: u32) -> u32 {
+  let state = value * 747796405u + 2891336453u;
+  let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+  return (word >> 22u) ^ word;
+}
+
+fn random(state: ptr<function, u32>) -> f32 {
+  *state = hash(*state);
+  return f32(*state) / 4294967295.0;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+  let index = global_id.x;
+  if (index >= arrayLength(&instances)) {
+    return;
+  }
+
+  var instance = instances[index];
+
+  instance.age = instance.age * uniforms.age_scale + uniforms.delta_time;
+  instance.position += instance.velocity * uniforms.delta_time;
+
+  if (uniforms.lifetime > 0.0 && instance.age >= uniforms.lifetime) {
+    var state = hash(index ^ hash(uniforms.seed));
+
+    instance.position = mix(
+      uniforms.position_range_min,
+      uniforms.position_range_max,
+      vec3<f32>(random(&state), random(&state), random(&state))
+    );
+
+    let direction = vec3<f32>(random(&state), random(&state), random(&state)) * 2.0 - 1.0;
+    let speed = mix(uniforms.speed_range.x, uniforms.speed_range.y, random(&state));
+    instance.velocity = normalize(direction) * speed;
+
+    instance.age = instance.age - uniforms.lifetime;
+  }
+
+  instances[index] = instance;
+}
@@ -0,0 +1,255 @@
+use crate::{
+    component::{Bloom, Effect},
+    entity::Scene,
+    renderer::render_graph::{
+        begin_render_pass, Node, NodeTextures, RenderGraph, TextureDescriptor, TextureId,
+    },
+    window::Size,
+};
+
+use super::{
+    AddRenderPass, BlurDownsampleRenderPass, BlurUpsampleRenderPass, BrightPassRenderPass,
+};
+
+const HDR_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// Bright pass, downsample/upsample mip chain and an add of the result onto the input. index is
+// the position of the effect in post_processing.effects.
+pub fn add_bloom(
+    graph: &mut RenderGraph,
+    index: usize,
+    iterations: u8,
+    input: TextureId,
+    size: Size,
+) -> TextureId {
+    let bright_pass = graph.add_texture(TextureDescriptor {
+        label: format!("Bloom {} Bright Pass Texture", index),
+        format: HDR_TEXTURE_FORMAT,
+        divisor: 1,
+    });
+    graph.add_pass(
+        format!("Bloom {} Bright Pass", index),
+        &[input],
+        &[bright_pass],
+        move |device, _, textures| {
+            Box::new(BrightPassNode {
+                index,
+                render_pass: BrightPassRenderPass::new(
+                    device,
+                    textures.reads[0].texture.wgpu_texture(),
+                    textures.writes[0].texture.format(),
+                ),
+            })
+        },
+    );
+
+    // Each level halves the previous one: 1/2, 1/4, 1/8, ...
+    let num_levels = level_count(size.width, size.height, iterations);
+    let level_divisor = |level: u32| 2 << level;
+
+    let mut src = bright_pass;
+    let mut downsample = Vec::new();
+    for i in 0..num_levels {
+        let dst = graph.add_texture(TextureDescriptor {
+            label: format!("Bloom {} Blur Downsample Texture {}", index, i),
+            format: HDR_TEXTURE_FORMAT,
+            divisor: level_divisor(i),
+        });
+        graph.add_pass(
+            format!("Bloom {} Blur Downsample {}", index, i),
+            &[src],
+            &[dst],
+            |device, _, textures| {
+                Box::new(BlurDownsampleNode(BlurDownsampleRenderPass::new(
+                    device,
+                    &textures.reads[0].texture,
+                    &textures.writes[0].texture,
+                )))
+            },
+        );
+        downsample.push(dst);
+        src = dst;
+    }
+
+    // Upsampling goes back up to the first downsample level, the smallest level is its source
+    for i in (0..num_levels - 1).rev() {
+        let base = downsample[i as usize];
+        let dst = graph.add_texture(TextureDescriptor {
+            label: format!("Bloom {} Blur Upsample Texture {}", index, i),
+            format: HDR_TEXTURE_FORMAT,
+            divisor: level_divisor(i),
+        });
+        graph.add_pass(
+            format!("Bloom {} Blur Upsample {}", index, i),
+            &[src, base],
+            &[dst],
+            move |device, _, textures| {
+                Box::new(BlurUpsampleNode {
+                    index,
+                    render_pass: BlurUpsampleRenderPass::new(
+                        device,
+                        &textures.reads[0].texture,
+                        &textures.reads[1].texture,
+                        &textures.writes[0].texture,
+                    ),
+                })
+            },
+        );
+        src = dst;
+    }
+
+    let output = graph.add_texture(TextureDescriptor {
+        label: format!("Bloom {} Output Texture", index),
+        format: HDR_TEXTURE_FORMAT,
+        divisor: 1,
+    });
+    graph.add_pass(
+        format!("Bloom {} Add", index),
+        &[input, src],
+        &[output],
+        |device, _, textures| {
+            Box::new(AddNode(AddRenderPass::new(
+                device,
+                &[
+                    &textures.reads[0].texture_view,
+                    &textures.reads[1].texture_view,
+                ],
+                textures.writes[0].texture.format(),
+            )))
+        },
+    );
+
+    output
+}
+
+// Bloom::iterations clamped so that the smallest level is still at least one pixel
+fn level_count(width: u32, height: u32, iterations: u8) -> u32 {
+    let max_levels = u32::BITS - 1 - width.min(height).max(1).leading_zeros();
+    (iterations as u32).clamp(1, max_levels.max(1))
+}
+
+fn bloom(scene: &Scene, index: usize) -> Option<&Bloom> {
+    match scene
+        .post_processing
+        .effects
+        .get(index)
+        .map(|effect| &effect.effect)
+    {
+        Some(Effect::Bloom(bloom)) => Some(bloom),
+        _ => None,
+    }
+}
+
+struct BrightPassNode {
+    index: usize,
+    render_pass: BrightPassRenderPass,
+}
+
+impl Node for BrightPassNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.render_pass
+            .recreate_bind_group(device, textures.reads[0].texture.wgpu_texture());
+    }
+
+    fn update(&mut self, _: &wgpu::Device, queue: &wgpu::Queue, scene: &Scene, _: f32) {
+        if let Some(bloom) = bloom(scene, self.index) {
+            self.render_pass.update(queue, bloom);
+        }
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pass.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.render_pass.draw(&mut begin_render_pass(
+            encoder,
+            "Bloom Bright Pass Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
+
+struct BlurDownsampleNode(BlurDownsampleRenderPass);
+
+impl Node for BlurDownsampleNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.0.recreate_bind_group(
+            device,
+            &textures.reads[0].texture,
+            &textures.writes[0].texture,
+        );
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.0.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.0.draw(&mut begin_render_pass(
+            encoder,
+            "Bloom Blur Downsample Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
+
+struct BlurUpsampleNode {
+    index: usize,
+    render_pass: BlurUpsampleRenderPass,
+}
+
+impl Node for BlurUpsampleNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.render_pass.recreate_bind_group(
+            device,
+            &textures.reads[0].texture,
+            &textures.reads[1].texture,
+            &textures.writes[0].texture,
+        );
+    }
+
+    fn update(&mut self, _: &wgpu::Device, queue: &wgpu::Queue, scene: &Scene, _: f32) {
+        if let Some(bloom) = bloom(scene, self.index) {
+            self.render_pass.update(queue, bloom);
+        }
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pass.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.render_pass.draw(&mut begin_render_pass(
+            encoder,
+            "Bloom Blur Upsample Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
+
+struct AddNode(AddRenderPass);
+
+impl Node for AddNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.0.recreate_bind_group(
+            device,
+            &[
+                &textures.reads[0].texture_view,
+                &textures.reads[1].texture_view,
+            ],
+        );
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.0.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.0.draw(&mut begin_render_pass(
+            encoder,
+            "Bloom Add Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
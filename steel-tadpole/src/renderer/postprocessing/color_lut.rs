@@ -0,0 +1,269 @@
+use std::{fs, num::NonZeroU32, path::Path};
+
+use anyhow::{bail, ensure, Context, Result};
+use bytemuck::cast_slice;
+use glam::{vec3, Vec3};
+use half::f16;
+
+// 3D color lookup table in the Adobe/Resolve .cube format. Entries are ordered with red changing
+// fastest, which is the x, y, z layout of the 3D texture it's uploaded to.
+pub struct ColorLut {
+    size: u32,
+    domain_min: Vec3,
+    domain_max: Vec3,
+    entries: Vec<Vec3>,
+}
+
+impl ColorLut {
+    // A 2x2x2 identity table is exact under trilinear filtering
+    pub fn identity() -> Self {
+        let entries = (0..8)
+            .map(|i| vec3((i & 1) as _, ((i >> 1) & 1) as _, ((i >> 2) & 1) as _))
+            .collect();
+
+        Self {
+            size: 2,
+            domain_min: Vec3::ZERO,
+            domain_max: Vec3::ONE,
+            entries,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read LUT file: {}", path.display()))?;
+
+        Self::parse(&source)
+            .with_context(|| format!("Failed to parse LUT file: {}", path.display()))
+    }
+
+    fn parse(source: &str) -> Result<Self> {
+        let mut size = None;
+        let mut domain_min = Vec3::ZERO;
+        let mut domain_max = Vec3::ONE;
+        let mut entries = Vec::new();
+
+        for (line_number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            let (keyword, arguments) = (tokens[0], &tokens[1..]);
+            let context = || format!("Invalid {} on line {}", keyword, line_number + 1);
+
+            match keyword {
+                "TITLE" => (),
+                "LUT_1D_SIZE" => bail!("1D LUTs are not supported"),
+                "LUT_3D_SIZE" => {
+                    size = Some(match arguments {
+                        [value] => value.parse::<u32>().with_context(context)?,
+                        _ => bail!(context()),
+                    })
+                }
+                "DOMAIN_MIN" => domain_min = parse_vec3(arguments).with_context(context)?,
+                "DOMAIN_MAX" => domain_max = parse_vec3(arguments).with_context(context)?,
+                // Written by some Resolve versions instead of DOMAIN_MIN and DOMAIN_MAX
+                "LUT_3D_INPUT_RANGE" => match parse_floats(arguments).with_context(context)?[..] {
+                    [min, max] => {
+                        domain_min = Vec3::splat(min);
+                        domain_max = Vec3::splat(max);
+                    }
+                    _ => bail!(context()),
+                },
+                _ if keyword.parse::<f32>().is_ok() => {
+                    entries.push(parse_vec3(&tokens).with_context(|| {
+                        format!("Invalid table entry on line {}", line_number + 1)
+                    })?)
+                }
+                // Unknown keywords are allowed by the format
+                _ => (),
+            }
+        }
+
+        let size = size.context("LUT_3D_SIZE is missing")?;
+        ensure!(size >= 2, "LUT_3D_SIZE must be at least 2");
+        ensure!(
+            entries.len() == (size * size * size) as usize,
+            "Expected {} table entries but found {}",
+            size * size * size,
+            entries.len()
+        );
+        ensure!(
+            domain_min.cmplt(domain_max).all(),
+            "DOMAIN_MIN must be less than DOMAIN_MAX"
+        );
+
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            entries,
+        })
+    }
+
+    pub fn domain(&self) -> (Vec3, Vec3) {
+        (self.domain_min, self.domain_max)
+    }
+
+    pub fn create_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+        let texels = self
+            .entries
+            .iter()
+            .flat_map(|entry| [entry.x, entry.y, entry.z, 1.0])
+            .map(f16::from_f32)
+            .collect::<Vec<_>>();
+
+        let texture_descriptor = wgpu::TextureDescriptor {
+            label: Some("Color LUT Texture"),
+            size: wgpu::Extent3d {
+                width: self.size,
+                height: self.size,
+                depth_or_array_layers: self.size,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        };
+
+        let texture = device.create_texture(&texture_descriptor);
+        queue.write_texture(
+            texture.as_image_copy(),
+            cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(self.size * 8),
+                rows_per_image: NonZeroU32::new(self.size),
+            },
+            texture_descriptor.size,
+        );
+
+        texture
+    }
+}
+
+fn parse_floats(tokens: &[&str]) -> Result<Vec<f32>> {
+    Ok(tokens
+        .iter()
+        .map(|token| token.parse::<f32>())
+        .collect::<Result<_, _>>()?)
+}
+
+fn parse_vec3(tokens: &[&str]) -> Result<Vec3> {
+    match parse_floats(tokens)?[..] {
+        [x, y, z] => Ok(vec3(x, y, z)),
+        _ => bail!("Expected 3 values but found {}", tokens.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_parse_error(source: &str, expected: &str) {
+        match ColorLut::parse(source) {
+            Ok(_) => panic!("Parsed an invalid LUT, expected: {}", expected),
+            Err(err) => assert!(
+                format!("{:#}", err).contains(expected),
+                "{:#} doesn't contain {}",
+                err,
+                expected
+            ),
+        }
+    }
+
+    #[test]
+    fn parses_3d_table() {
+        let lut = ColorLut::parse(
+            "TITLE \"Identity\"
+            # Red changes fastest
+            LUT_3D_SIZE 2
+            DOMAIN_MIN 0.0 0.0 0.0
+            DOMAIN_MAX 1.0 1.0 2.0
+
+            0.0 0.0 0.0
+            1.0 0.0 0.0
+            0.0 1.0 0.0
+            1.0 1.0 0.0
+            0.0 0.0 1.0
+            1.0 0.0 1.0
+            0.0 1.0 1.0
+            1.0 1.0 1.0",
+        )
+        .unwrap();
+
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.domain(), (Vec3::ZERO, vec3(1.0, 1.0, 2.0)));
+        assert_eq!(lut.entries, ColorLut::identity().entries);
+    }
+
+    #[test]
+    fn input_range_sets_the_domain() {
+        let lut = ColorLut::parse(
+            "LUT_3D_SIZE 2
+            LUT_3D_INPUT_RANGE -0.5 4.0
+            0 0 0
+            1 0 0
+            0 1 0
+            1 1 0
+            0 0 1
+            1 0 1
+            0 1 1
+            1 1 1",
+        )
+        .unwrap();
+
+        assert_eq!(lut.domain(), (Vec3::splat(-0.5), Vec3::splat(4.0)));
+    }
+
+    #[test]
+    fn rejects_1d_tables() {
+        assert_parse_error(
+            "LUT_1D_SIZE 2
+            0 0 0
+            1 1 1",
+            "1D LUTs are not supported",
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_entry_count() {
+        assert_parse_error(
+            "LUT_3D_SIZE 2
+            0 0 0
+            1 1 1",
+            "Expected 8 table entries but found 2",
+        );
+    }
+
+    #[test]
+    fn rejects_inverted_domain() {
+        assert_parse_error(
+            "LUT_3D_SIZE 2
+            DOMAIN_MIN 1 1 1
+            DOMAIN_MAX 0 1 2
+            0 0 0
+            1 0 0
+            0 1 0
+            1 1 0
+            0 0 1
+            1 0 1
+            0 1 1
+            1 1 1",
+            "DOMAIN_MIN must be less than DOMAIN_MAX",
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_parse_error("LUT_3D_SIZE two", "Invalid LUT_3D_SIZE on line 1");
+        assert_parse_error(
+            "LUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE 0 1 2",
+            "Invalid LUT_3D_INPUT_RANGE on line 2",
+        );
+        assert_parse_error("LUT_3D_SIZE 2\n0 0", "Invalid table entry on line 2");
+    }
+}
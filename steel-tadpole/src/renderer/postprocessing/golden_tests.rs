@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use glam::{vec3, Quat, Vec3};
+use image::{Rgba, RgbaImage};
+use pollster::FutureExt as _;
+
+use crate::{
+    component::{self, Effect, Particle, PostProcessEffect, Tonemapper, Transform},
+    entity::{self, Camera, PostProcessing, Scene},
+    renderer::{Frame, Renderer},
+    scene_graph::EntityGraph,
+    window::Size,
+};
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 96;
+const NUM_BLOOM_LEVELS: u8 = 4;
+
+// Maximum per-channel difference allowed between a rendered pixel and its reference
+const TOLERANCE: u8 = 2;
+
+// Particles that stand still and never respawn, so that the first frame is always the same
+fn spots(
+    count: u32,
+    size: f32,
+    position_range: (Vec3, Vec3),
+    color_range: (Vec3, Vec3),
+) -> Particle {
+    Particle {
+        max_count: count,
+        particle_size: size,
+        lifetime: 0.0,
+        speed_range: (0.0, 0.0),
+        color_range,
+        position_range,
+        seed: Some(1),
+    }
+}
+
+fn spot(size: f32, color: Vec3) -> Particle {
+    spots(1, size, (Vec3::ZERO, Vec3::ZERO), (color, color))
+}
+
+fn transform(position: Vec3) -> Transform {
+    Transform {
+        position,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    }
+}
+
+// The particles are 10 units in front of the camera, where a unit is about 8 pixels
+fn scene(particle: Particle, exposure: f32) -> Scene {
+    let mut scene = Scene {
+        camera: Camera {
+            transform: transform(Vec3::ZERO),
+            camera: component::Camera {
+                fov: 60.0,
+                aspect_ratio: WIDTH as f32 / HEIGHT as f32,
+                near: 0.1,
+                far: 100.0,
+                exposure,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        particle: entity::Particle {
+            transform: transform(vec3(0.0, 0.0, 10.0)),
+            particle,
+            ..Default::default()
+        },
+        post_processing: PostProcessing {
+            effects: vec![
+                PostProcessEffect {
+                    enabled: true,
+                    effect: Effect::Bloom(component::Bloom {
+                        threshold: 1.0,
+                        intensity: 1.0,
+                        knee: 0.5,
+                        scatter: 0.7,
+                        iterations: NUM_BLOOM_LEVELS,
+                    }),
+                },
+                PostProcessEffect {
+                    enabled: true,
+                    effect: Effect::Tonemap(Tonemapper::AcesFilmic),
+                },
+            ],
+        },
+        ..Default::default()
+    };
+    EntityGraph::new().update(&mut scene);
+    scene
+}
+
+fn render(scene: &Scene) -> Result<RgbaImage> {
+    let size = Size {
+        width: WIDTH,
+        height: HEIGHT,
+    };
+
+    // Reference images are rendered with the software adapter so that they don't depend on the GPU
+    let mut renderer = Renderer::new_headless(size, scene, true).block_on()?;
+    renderer.render(scene, 0.0)?;
+    let Frame { size, data } = renderer.read_output().block_on()?;
+
+    RgbaImage::from_raw(size.width, size.height, data).context("Output has an unexpected size")
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn failure_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden")
+}
+
+// Set UPDATE_GOLDEN=1 to (re)generate the reference images instead of comparing against them.
+// The tests are ignored until the references are regenerated for the current bloom and
+// tonemapping, `cargo test -- --ignored` runs them.
+fn assert_matches_golden(name: &str, actual: &RgbaImage) -> Result<()> {
+    let golden_path = golden_dir().join(format!("{}.png", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(golden_dir())?;
+        actual.save(&golden_path)?;
+        return Ok(());
+    }
+
+    let expected = image::open(&golden_path)
+        .with_context(|| {
+            format!(
+                "Failed to open {}, run with UPDATE_GOLDEN=1 to create it",
+                golden_path.display()
+            )
+        })?
+        .to_rgba8();
+
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "{}: size differs from the reference image",
+        name
+    );
+
+    let mut mismatched_pixels = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let actual = actual.get_pixel(x, y);
+        let expected = expected.get_pixel(x, y);
+        let max_difference = std::iter::zip(actual.0, expected.0)
+            .map(|(a, e)| a.abs_diff(e))
+            .max()
+            .unwrap_or(0);
+        if max_difference > TOLERANCE {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = expected.0;
+            Rgba([r / 4, g / 4, b / 4, 255])
+        }
+    });
+
+    if mismatched_pixels > 0 {
+        std::fs::create_dir_all(failure_dir())?;
+        let actual_path = failure_dir().join(format!("{}.actual.png", name));
+        let diff_path = failure_dir().join(format!("{}.diff.png", name));
+        actual.save(&actual_path)?;
+        diff.save(&diff_path)?;
+
+        panic!(
+            "{}: {} pixels differ from {} by more than {}, see {} and {}",
+            name,
+            mismatched_pixels,
+            golden_path.display(),
+            TOLERANCE,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "stale references, regenerate with UPDATE_GOLDEN=1 on the fallback adapter"]
+fn bloom_single_spot() -> Result<()> {
+    let image = render(&scene(spot(0.5, Vec3::ONE * 8.0), 1.0))?;
+    assert_matches_golden("bloom_single_spot", &image)
+}
+
+#[test]
+#[ignore = "stale references, regenerate with UPDATE_GOLDEN=1 on the fallback adapter"]
+fn bloom_below_threshold() -> Result<()> {
+    let image = render(&scene(spot(2.0, Vec3::ONE * 0.4), 1.0))?;
+    assert_matches_golden("bloom_below_threshold", &image)
+}
+
+#[test]
+#[ignore = "stale references, regenerate with UPDATE_GOLDEN=1 on the fallback adapter"]
+fn bloom_colored_spots() -> Result<()> {
+    let particle = spots(
+        3,
+        0.6,
+        (vec3(-5.0, -3.0, 0.0), vec3(5.0, 3.0, 0.0)),
+        (Vec3::ONE * 0.5, Vec3::ONE * 10.0),
+    );
+    let image = render(&scene(particle, 1.0))?;
+    assert_matches_golden("bloom_colored_spots", &image)
+}
+
+#[test]
+#[ignore = "stale references, regenerate with UPDATE_GOLDEN=1 on the fallback adapter"]
+fn bloom_low_exposure() -> Result<()> {
+    let image = render(&scene(spot(0.5, Vec3::ONE * 8.0), 0.25))?;
+    assert_matches_golden("bloom_low_exposure", &image)
+}
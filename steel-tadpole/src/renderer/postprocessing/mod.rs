@@ -1,4 +1,11 @@
 mod bloom_effect;
+mod color_lut;
 mod render_pass;
+mod stack;
 
+#[cfg(test)]
+mod golden_tests;
+
+pub use color_lut::ColorLut;
 pub use render_pass::*;
+pub use stack::PostProcessStack;
@@ -1,5 +1,9 @@
+use common::shader::{include_shader, ReloadablePipeline};
+
 pub struct AddRenderPass {
-    render_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
     bind_group: wgpu::BindGroup,
 }
 
@@ -55,38 +59,72 @@ impl AddRenderPass {
                 push_constant_ranges: &[],
             });
 
-            let vertex_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("fullscreen.vertex.wgsl"));
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("add.fragment.wgsl"));
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen.vertex.wgsl"),
+                    include_shader!("add.fragment.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[render_target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[render_target_format.into()],
-                }),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &sampler, texture_views);
+
+        Self {
+            sampler,
+            bind_group_layout,
+            render_pipeline,
+            bind_group,
+        }
+    }
+
+    pub fn recreate_bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        texture_views: &[&wgpu::TextureView; 2],
+    ) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            texture_views,
+        );
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        texture_views: &[&wgpu::TextureView; 2],
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
-            layout: &bind_group_layout,
+            layout: bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
+                    resource: wgpu::BindingResource::Sampler(sampler),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
@@ -97,16 +135,15 @@ impl AddRenderPass {
                     resource: wgpu::BindingResource::TextureView(texture_views[1]),
                 },
             ],
-        });
+        })
+    }
 
-        Self {
-            render_pipeline,
-            bind_group,
-        }
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
     }
 
     pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
-        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_pipeline(self.render_pipeline.get());
         rpass.set_bind_group(0, &self.bind_group, &[]);
         rpass.draw(0..3, 0..1);
     }
@@ -1,6 +1,7 @@
 use std::mem::size_of;
 
 use bytemuck::{bytes_of, Pod, Zeroable};
+use common::shader::{include_shader, ReloadablePipeline};
 use glam::{vec2, Vec2};
 use wgpu::util::DeviceExt;
 
@@ -13,7 +14,9 @@ struct Uniforms {
 }
 
 pub struct BlurDownsampleRenderPass {
-    render_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
     bind_group: wgpu::BindGroup,
 }
 
@@ -63,80 +66,121 @@ impl BlurDownsampleRenderPass {
         });
 
         let render_pipeline = {
+            let render_target_format = render_target_texture.format();
+
             let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: None,
                 bind_group_layouts: &[&bind_group_layout],
                 push_constant_ranges: &[],
             });
 
-            let vertex_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("fullscreen.vertex.wgsl"));
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("blur_downsample.fragment.wgsl"));
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen.vertex.wgsl"),
+                    include_shader!("blur_downsample.fragment.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[render_target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[render_target_texture.format().into()],
-                }),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
-        let bind_group = {
-            let src_texture_view = src_texture
-                .as_ref()
-                .create_view(&wgpu::TextureViewDescriptor::default());
-
-            let resolution = vec2(
-                render_target_texture.width() as _,
-                render_target_texture.height() as _,
-            );
-            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Blur Downsample Uniform Buffer"),
-                contents: bytes_of(&Uniforms { resolution }),
-                usage: wgpu::BufferUsages::UNIFORM,
-            });
-
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::Sampler(&sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&src_texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: uniform_buffer.as_entire_binding(),
-                    },
-                ],
-            })
-        };
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &sampler,
+            src_texture,
+            render_target_texture,
+        );
 
         Self {
+            sampler,
+            bind_group_layout,
             render_pipeline,
             bind_group,
         }
     }
 
+    pub fn recreate_bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        src_texture: &wgpu_ext::Texture,
+        render_target_texture: &wgpu_ext::Texture,
+    ) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            src_texture,
+            render_target_texture,
+        );
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        src_texture: &wgpu_ext::Texture,
+        render_target_texture: &wgpu_ext::Texture,
+    ) -> wgpu::BindGroup {
+        let src_texture_view = src_texture
+            .as_ref()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let resolution = vec2(
+            render_target_texture.width() as _,
+            render_target_texture.height() as _,
+        );
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Blur Downsample Uniform Buffer"),
+            contents: bytes_of(&Uniforms { resolution }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&src_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
     pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
-        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_pipeline(self.render_pipeline.get());
         rpass.set_bind_group(0, &self.bind_group, &[]);
         rpass.draw(0..3, 0..1);
     }
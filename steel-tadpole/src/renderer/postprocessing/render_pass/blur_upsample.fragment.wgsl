@@ -1,5 +1,6 @@
 struct Uniforms {
   resolution: vec2<f32>,
+  scatter: f32,
 }
 
 @group(0) @binding(0)
@@ -8,6 +9,8 @@ var r_sampler: sampler;
 var r_texture: texture_2d<f32>;
 @group(0) @binding(2)
 var<uniform> r_uniforms: Uniforms;
+@group(0) @binding(3)
+var r_base_texture: texture_2d<f32>;
 
 @fragment
 fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
@@ -54,6 +57,8 @@ fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
     tex_coord + vec2<f32>(-half_texel.x, -half_texel.y)
   ) * 2.0;
 
-  return color / 12.0;
+  // Blend the upsampled lower resolution level over this level's downsample
+  let base = textureSample(r_base_texture, r_sampler, tex_coord);
+  return mix(base, color / 12.0, r_uniforms.scatter);
 }
 
@@ -1,19 +1,25 @@
 use std::mem::size_of;
 
 use bytemuck::{bytes_of, Pod, Zeroable};
+use common::shader::{include_shader, ReloadablePipeline};
 use glam::{vec2, Vec2};
-use wgpu::util::DeviceExt;
 
-use crate::renderer::wgpu_ext;
+use crate::{component::Bloom, renderer::wgpu_ext};
 
 #[derive(Debug, Copy, Clone, PartialEq, Default, Pod, Zeroable)]
 #[repr(C)]
 struct Uniforms {
     resolution: Vec2,
+    scatter: f32,
+    _padding: f32,
 }
 
 pub struct BlurUpsampleRenderPass {
-    render_pipeline: wgpu::RenderPipeline,
+    resolution: Vec2,
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
     bind_group: wgpu::BindGroup,
 }
 
@@ -21,6 +27,7 @@ impl BlurUpsampleRenderPass {
     pub fn new(
         device: &wgpu::Device,
         src_texture: &wgpu_ext::Texture,
+        base_texture: &wgpu_ext::Texture,
         render_target_texture: &wgpu_ext::Texture,
     ) -> Self {
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -30,6 +37,13 @@ impl BlurUpsampleRenderPass {
             ..Default::default()
         });
 
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Blur Upsample Uniform Buffer"),
+            size: size_of::<Uniforms>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: None,
             entries: &[
@@ -59,85 +73,153 @@ impl BlurUpsampleRenderPass {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
             ],
         });
 
         let render_pipeline = {
+            let render_target_format = render_target_texture.format();
+
             let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: None,
                 bind_group_layouts: &[&bind_group_layout],
                 push_constant_ranges: &[],
             });
 
-            let vertex_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("fullscreen.vertex.wgsl"));
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("blur_upsample.fragment.wgsl"));
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen.vertex.wgsl"),
+                    include_shader!("blur_upsample.fragment.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[render_target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[render_target_texture.format().into()],
-                }),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
-        let bind_group = {
-            let src_texture_view = src_texture
-                .as_ref()
-                .create_view(&wgpu::TextureViewDescriptor::default());
-
-            let resolution = vec2(
-                render_target_texture.width() as _,
-                render_target_texture.height() as _,
-            );
-            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Blur Downsample Uniform Buffer"),
-                contents: bytes_of(&Uniforms { resolution }),
-                usage: wgpu::BufferUsages::UNIFORM,
-            });
-
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::Sampler(&sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&src_texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: uniform_buffer.as_entire_binding(),
-                    },
-                ],
-            })
-        };
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &sampler,
+            src_texture,
+            base_texture,
+        );
 
         Self {
+            resolution: texture_resolution(render_target_texture),
+            uniform_buffer,
+            sampler,
+            bind_group_layout,
             render_pipeline,
             bind_group,
         }
     }
 
+    pub fn recreate_bind_group(
+        &mut self,
+        device: &wgpu::Device,
+        src_texture: &wgpu_ext::Texture,
+        base_texture: &wgpu_ext::Texture,
+        render_target_texture: &wgpu_ext::Texture,
+    ) {
+        self.resolution = texture_resolution(render_target_texture);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            &self.sampler,
+            src_texture,
+            base_texture,
+        );
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue, bloom: &Bloom) {
+        let uniforms = Uniforms {
+            resolution: self.resolution,
+            scatter: bloom.scatter,
+            _padding: 0.0,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytes_of(&uniforms));
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        src_texture: &wgpu_ext::Texture,
+        base_texture: &wgpu_ext::Texture,
+    ) -> wgpu::BindGroup {
+        let src_texture_view = src_texture
+            .as_ref()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let base_texture_view = base_texture
+            .as_ref()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&src_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&base_texture_view),
+                },
+            ],
+        })
+    }
+
     pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
-        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_pipeline(self.render_pipeline.get());
         rpass.set_bind_group(0, &self.bind_group, &[]);
         rpass.draw(0..3, 0..1);
     }
 }
+
+fn texture_resolution(texture: &wgpu_ext::Texture) -> Vec2 {
+    vec2(texture.width() as _, texture.height() as _)
+}
@@ -1,5 +1,6 @@
 struct Uniforms {
   threshold: f32,
+  knee: f32,
   intensity: f32,
 }
 
@@ -12,6 +13,17 @@ var r_texture: texture_2d<f32>;
 
 @fragment
 fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
-  var color = textureSample(r_texture, r_sampler, tex_coord);
-  return color * step(r_uniforms.threshold, length(color));
+  let color = textureSample(r_texture, r_sampler, tex_coord);
+  // Soft-knee threshold on luminance, keeps the hue and fades in over [threshold - knee, threshold + knee].
+  // Without a knee it's a hard cutoff at the threshold.
+  let brightness = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+  let threshold = r_uniforms.threshold;
+  let knee = r_uniforms.knee;
+  let contribution = select(
+    step(threshold, brightness),
+    smoothstep(threshold - knee, threshold + knee, brightness),
+    knee > 0.0
+  );
+  // Blurring is linear, so scaling here is the same as scaling the final bloom
+  return vec4<f32>(color.rgb * contribution * r_uniforms.intensity, color.a);
 }
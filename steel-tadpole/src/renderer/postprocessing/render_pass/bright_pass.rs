@@ -1,28 +1,35 @@
 use std::mem::size_of;
 
 use bytemuck::{bytes_of, Pod, Zeroable};
+use common::shader::{include_shader, ReloadablePipeline};
 
-use crate::entity::Scene;
+use crate::component::Bloom;
 
 #[derive(Debug, Copy, Clone, PartialEq, Default, Pod, Zeroable)]
 #[repr(C)]
 struct Uniforms {
     threshold: f32,
+    knee: f32,
     intensity: f32,
+    _pad0: [u8; 4],
 }
 
 impl Uniforms {
-    fn new(scene: &Scene) -> Self {
+    fn new(bloom: &Bloom) -> Self {
         Self {
-            intensity: scene.post_processing.bloom.intensity,
-            threshold: scene.post_processing.bloom.threshold,
+            threshold: bloom.threshold,
+            knee: bloom.knee,
+            intensity: bloom.intensity,
+            ..Default::default()
         }
     }
 }
 
 pub struct BrightPassRenderPass {
     uniform_buffer: wgpu::Buffer,
-    render_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
     bind_group: wgpu::BindGroup,
 }
 
@@ -85,37 +92,82 @@ impl BrightPassRenderPass {
                 push_constant_ranges: &[],
             });
 
-            let vertex_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("fullscreen.vertex.wgsl"));
-
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("bright_pass.fragment.wgsl"));
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen.vertex.wgsl"),
+                    include_shader!("bright_pass.fragment.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[color_target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[color_target_format.into()],
-                }),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &sampler,
+            src_texture,
+        );
+
+        Self {
+            uniform_buffer,
+            sampler,
+            bind_group_layout,
+            render_pipeline,
+            bind_group,
+        }
+    }
+
+    pub fn recreate_bind_group(&mut self, device: &wgpu::Device, src_texture: &wgpu::Texture) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            &self.sampler,
+            src_texture,
+        );
+    }
+
+    pub fn update(&self, queue: &wgpu::Queue, bloom: &Bloom) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytes_of(&Uniforms::new(bloom)));
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        src_texture: &wgpu::Texture,
+    ) -> wgpu::BindGroup {
         let src_texture_view = src_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
-            layout: &bind_group_layout,
+            layout: bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -123,29 +175,128 @@ impl BrightPassRenderPass {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
+                    resource: wgpu::BindingResource::Sampler(sampler),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
                     resource: wgpu::BindingResource::TextureView(&src_texture_view),
                 },
             ],
-        });
+        })
+    }
 
-        Self {
-            uniform_buffer,
-            render_pipeline,
-            bind_group,
+    pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
+        rpass.set_pipeline(self.render_pipeline.get());
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, Result};
+    use half::f16;
+    use pollster::FutureExt as _;
+    use wgpu::util::DeviceExt as _;
+
+    use crate::renderer::{
+        render_graph::begin_render_pass,
+        wgpu_ext::{self, DeviceExt as _},
+    };
+
+    use super::*;
+
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+    fn texture_descriptor(
+        width: u32,
+        usage: wgpu::TextureUsages,
+    ) -> wgpu::TextureDescriptor<'static> {
+        wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage,
         }
     }
 
-    pub fn update(&self, queue: &wgpu::Queue, scene: &Scene) {
-        queue.write_buffer(&self.uniform_buffer, 0, bytes_of(&Uniforms::new(scene)));
+    // Runs the bright pass over a row of gray pixels and returns the resulting gray levels
+    fn bright_pass(levels: &[f32], bloom: &Bloom) -> Result<Vec<f32>> {
+        let backends = wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::PRIMARY);
+        let instance = wgpu::Instance::new(backends);
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                force_fallback_adapter: true,
+                ..Default::default()
+            })
+            .block_on()
+            .context("No adapter found")?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .block_on()?;
+
+        let width = levels.len() as u32;
+        let pixels: Vec<f16> = levels
+            .iter()
+            .flat_map(|&level| [level, level, level, 1.0])
+            .map(f16::from_f32)
+            .collect();
+        let src_texture = device.create_texture_with_data(
+            &queue,
+            &texture_descriptor(width, wgpu::TextureUsages::TEXTURE_BINDING),
+            bytemuck::cast_slice(&pixels),
+        );
+        let dst_texture = device.create_texture_ext(&texture_descriptor(
+            width,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        ));
+
+        let render_pass = BrightPassRenderPass::new(&device, &src_texture, FORMAT);
+        render_pass.update(&queue, bloom);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let dst_view = dst_texture
+            .wgpu_texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        render_pass.draw(&mut begin_render_pass(
+            &mut encoder,
+            "Bright Pass",
+            &dst_view,
+        ));
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let data = wgpu_ext::read_texture(&device, &queue, &dst_texture).block_on()?;
+        Ok(data
+            .chunks_exact(8)
+            .map(|pixel| f16::from_le_bytes([pixel[0], pixel[1]]).to_f32())
+            .collect())
     }
 
-    pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
-        rpass.set_pipeline(&self.render_pipeline);
-        rpass.set_bind_group(0, &self.bind_group, &[]);
-        rpass.draw(0..3, 0..1);
+    #[test]
+    fn zero_knee_is_a_hard_threshold() -> Result<()> {
+        let bloom = Bloom {
+            threshold: 1.0,
+            knee: 0.0,
+            intensity: 1.0,
+            ..Default::default()
+        };
+        let levels = [0.5, 0.99, 1.01, 4.0];
+
+        // Pixels below the threshold are dropped and the others pass unchanged, like the cutoff
+        // from before the knee
+        let expected: Vec<f32> = levels
+            .iter()
+            .map(|&level| if level >= bloom.threshold { level } else { 0.0 })
+            .map(|level| f16::from_f32(level).to_f32())
+            .collect();
+        assert_eq!(bright_pass(&levels, &bloom)?, expected);
+        Ok(())
     }
 }
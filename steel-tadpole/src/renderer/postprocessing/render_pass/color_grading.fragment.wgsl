@@ -0,0 +1,59 @@
+struct Uniforms {
+  white_balance: mat4x4<f32>,
+  lift: vec3<f32>,
+  saturation: f32,
+  gamma: vec3<f32>,
+  contrast: f32,
+  gain: vec3<f32>,
+  lut_domain_min: vec3<f32>,
+  lut_domain_max: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> r_uniforms: Uniforms;
+@group(0) @binding(1)
+var r_sampler: sampler;
+@group(0) @binding(2)
+var r_texture: texture_2d<f32>;
+@group(1) @binding(0)
+var r_lut_texture: texture_3d<f32>;
+
+fn grade(color: vec3<f32>) -> vec3<f32> {
+  var graded = (r_uniforms.white_balance * vec4<f32>(color, 0.0)).rgb;
+  // Contrast pivots around middle grey
+  graded = 0.18 * pow(max(graded, vec3<f32>(0.0)) / 0.18, vec3<f32>(r_uniforms.contrast));
+  graded = r_uniforms.gain * (graded + r_uniforms.lift * (vec3<f32>(1.0) - graded));
+  graded = pow(max(graded, vec3<f32>(0.0)), vec3<f32>(1.0) / r_uniforms.gamma);
+  let luminance = dot(graded, vec3<f32>(0.2126, 0.7152, 0.0722));
+  return mix(vec3<f32>(luminance), graded, r_uniforms.saturation);
+}
+
+fn apply_lut(color: vec3<f32>) -> vec3<f32> {
+  let size = vec3<f32>(textureDimensions(r_lut_texture));
+  let domain = r_uniforms.lut_domain_max - r_uniforms.lut_domain_min;
+  let coord = clamp((color - r_uniforms.lut_domain_min) / domain, vec3<f32>(0.0), vec3<f32>(1.0));
+  // Map the domain onto the centers of the outermost texels
+  return textureSample(r_lut_texture, r_sampler, (coord * (size - 1.0) + 0.5) / size).rgb;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+  let low = color * 12.92;
+  let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+  return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+  let low = color / 12.92;
+  let high = pow((color + vec3<f32>(0.055)) / 1.055, vec3<f32>(2.4));
+  return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+  var color = textureSample(r_texture, r_sampler, tex_coord).rgb;
+  color = clamp(grade(color), vec3<f32>(0.0), vec3<f32>(1.0));
+  // LUTs from grading tools expect display encoded input and produce display encoded output
+  color = apply_lut(linear_to_srgb(color));
+  // Later effects work on linear colors, the output pass encodes for the surface
+  return vec4<f32>(srgb_to_linear(color), 1.0);
+}
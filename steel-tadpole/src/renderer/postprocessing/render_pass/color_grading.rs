@@ -0,0 +1,281 @@
+use std::{mem::size_of, path::PathBuf};
+
+use bytemuck::{bytes_of, Pod, Zeroable};
+use common::shader::{include_shader, ReloadablePipeline};
+use glam::{Mat4, Vec3};
+use log::error;
+
+use crate::{component::ColorGrading, renderer::postprocessing::ColorLut};
+
+#[derive(Debug, Copy, Clone, PartialEq, Default, Pod, Zeroable)]
+#[repr(C)]
+struct Uniforms {
+    white_balance: Mat4,
+    lift: Vec3,
+    saturation: f32,
+    gamma: Vec3,
+    contrast: f32,
+    gain: Vec3,
+    _pad0: [u8; 4],
+    lut_domain_min: Vec3,
+    _pad1: [u8; 4],
+    lut_domain_max: Vec3,
+    _pad2: [u8; 4],
+}
+
+impl Uniforms {
+    fn new(color_grading: &ColorGrading, (lut_domain_min, lut_domain_max): (Vec3, Vec3)) -> Self {
+        Self {
+            white_balance: Mat4::from_mat3(color_grading.white_balance()),
+            lift: color_grading.lift,
+            saturation: color_grading.saturation,
+            gamma: color_grading.gamma,
+            contrast: color_grading.contrast,
+            gain: color_grading.gain,
+            lut_domain_min,
+            lut_domain_max,
+            ..Default::default()
+        }
+    }
+}
+
+pub struct ColorGradingRenderPass {
+    lut_path: Option<PathBuf>,
+    lut_domain: (Vec3, Vec3),
+    uniform_buffer: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    lut_bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
+    bind_group: wgpu::BindGroup,
+    lut_bind_group: wgpu::BindGroup,
+}
+
+impl ColorGradingRenderPass {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        src_texture: &wgpu::Texture,
+        render_target_format: wgpu::TextureFormat,
+    ) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Color Grading Bilinear Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Color Grading Uniform Buffer"),
+            size: size_of::<Uniforms>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<Uniforms>() as _),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Kept in its own group so that loading another LUT doesn't touch the render targets
+        let lut_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let render_pipeline = {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout, &lut_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen.vertex.wgsl"),
+                    include_shader!("color_grading.fragment.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: Some("Color Grading Render Pipeline"),
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[render_target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
+                },
+            )
+        };
+
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &sampler,
+            src_texture,
+        );
+
+        let lut = ColorLut::identity();
+        let lut_bind_group =
+            Self::create_lut_bind_group(device, queue, &lut_bind_group_layout, &lut);
+
+        Self {
+            lut_path: None,
+            lut_domain: lut.domain(),
+            uniform_buffer,
+            sampler,
+            bind_group_layout,
+            lut_bind_group_layout,
+            render_pipeline,
+            bind_group,
+            lut_bind_group,
+        }
+    }
+
+    pub fn recreate_bind_group(&mut self, device: &wgpu::Device, src_texture: &wgpu::Texture) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            &self.sampler,
+            src_texture,
+        );
+    }
+
+    // Also loads the LUT when its path changed, falling back to the identity if that fails
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color_grading: &ColorGrading,
+    ) {
+        if self.lut_path != color_grading.lut {
+            self.lut_path = color_grading.lut.clone();
+            let lut = match &self.lut_path {
+                Some(path) => ColorLut::load(path).unwrap_or_else(|err| {
+                    error!("Failed to load color LUT: {:?}", err);
+                    ColorLut::identity()
+                }),
+                None => ColorLut::identity(),
+            };
+            self.lut_domain = lut.domain();
+            self.lut_bind_group =
+                Self::create_lut_bind_group(device, queue, &self.lut_bind_group_layout, &lut);
+        }
+
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytes_of(&Uniforms::new(color_grading, self.lut_domain)),
+        );
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        src_texture: &wgpu::Texture,
+    ) -> wgpu::BindGroup {
+        let src_texture_view = src_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&src_texture_view),
+                },
+            ],
+        })
+    }
+
+    fn create_lut_bind_group(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lut_bind_group_layout: &wgpu::BindGroupLayout,
+        lut: &ColorLut,
+    ) -> wgpu::BindGroup {
+        let lut_texture_view = lut
+            .create_texture(device, queue)
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: lut_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&lut_texture_view),
+            }],
+        })
+    }
+
+    pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
+        rpass.set_pipeline(self.render_pipeline.get());
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_bind_group(1, &self.lut_bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
@@ -1,15 +1,17 @@
 mod add;
-mod blur;
 mod blur_downsample;
 mod blur_upsample;
 mod bright_pass;
-mod compose;
-mod copy;
+mod color_grading;
+mod output;
+mod tonemap;
+mod vignette;
 
 pub use add::AddRenderPass;
-pub use blur::BlurRenderPass;
 pub use blur_downsample::BlurDownsampleRenderPass;
 pub use blur_upsample::BlurUpsampleRenderPass;
 pub use bright_pass::BrightPassRenderPass;
-pub use compose::ComposeRenderPass;
-pub use copy::CopyRenderPass;
+pub use color_grading::ColorGradingRenderPass;
+pub use output::OutputRenderPass;
+pub use tonemap::TonemapRenderPass;
+pub use vignette::VignetteRenderPass;
@@ -0,0 +1,25 @@
+struct Uniforms {
+  encode_srgb: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> r_uniforms: Uniforms;
+@group(0) @binding(1)
+var r_sampler: sampler;
+@group(0) @binding(2)
+var r_texture: texture_2d<f32>;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+  let low = color * 12.92;
+  let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+  return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+  var color = clamp(textureSample(r_texture, r_sampler, tex_coord).rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+  if (r_uniforms.encode_srgb == 1u) {
+    color = linear_to_srgb(color);
+  }
+  return vec4<f32>(color, 1.0);
+}
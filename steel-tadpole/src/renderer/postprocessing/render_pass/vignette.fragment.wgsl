@@ -0,0 +1,20 @@
+struct Uniforms {
+  intensity: f32,
+  smoothness: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> r_uniforms: Uniforms;
+@group(0) @binding(1)
+var r_sampler: sampler;
+@group(0) @binding(2)
+var r_texture: texture_2d<f32>;
+
+@fragment
+fn main(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+  let color = textureSample(r_texture, r_sampler, tex_coord);
+  // 0 at the center and 1 at the corners
+  let radius = length(tex_coord - vec2<f32>(0.5)) * sqrt(2.0);
+  let falloff = smoothstep(1.0 - r_uniforms.smoothness, 1.0, radius);
+  return vec4<f32>(color.rgb * (1.0 - r_uniforms.intensity * falloff), color.a);
+}
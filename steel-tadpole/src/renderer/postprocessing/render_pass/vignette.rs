@@ -1,45 +1,51 @@
 use std::mem::size_of;
 
 use bytemuck::{bytes_of, Pod, Zeroable};
+use common::shader::{include_shader, ReloadablePipeline};
 
-use crate::entity::Scene;
+use crate::component::Vignette;
 
 #[derive(Debug, Copy, Clone, PartialEq, Default, Pod, Zeroable)]
 #[repr(C)]
 struct Uniforms {
-    exposure: f32,
+    intensity: f32,
+    smoothness: f32,
+    _pad0: [u8; 8],
 }
 
 impl Uniforms {
-    fn new(scene: &Scene) -> Self {
+    fn new(vignette: &Vignette) -> Self {
         Self {
-            exposure: scene.camera.camera.exposure,
+            intensity: vignette.intensity,
+            smoothness: vignette.smoothness,
+            ..Default::default()
         }
     }
 }
 
-pub struct ComposeRenderPass {
+pub struct VignetteRenderPass {
     uniform_buffer: wgpu::Buffer,
-    render_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: ReloadablePipeline<wgpu::RenderPipeline>,
     bind_group: wgpu::BindGroup,
 }
 
-impl ComposeRenderPass {
+impl VignetteRenderPass {
     pub fn new(
         device: &wgpu::Device,
-        color_texture: &wgpu::Texture,
-        bloom_texture: &wgpu::Texture,
-        render_target_format: wgpu::TextureFormat,
+        src_texture: &wgpu::Texture,
+        color_target_format: wgpu::TextureFormat,
     ) -> Self {
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Compose Render Pass Bilinear Sampler"),
+            label: Some("Vignette Bilinear Sampler"),
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
 
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Bright Pass Uniform Buffer"),
+            label: Some("Vignette Uniform Buffer"),
             size: size_of::<Uniforms>() as _,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
@@ -74,16 +80,6 @@ impl ComposeRenderPass {
                     },
                     count: None,
                 },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
             ],
         });
 
@@ -94,38 +90,82 @@ impl ComposeRenderPass {
                 push_constant_ranges: &[],
             });
 
-            let vertex_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("fullscreen.vertex.wgsl"));
-            let fragment_shader_module =
-                device.create_shader_module(&wgpu::include_wgsl!("compose.fragment.wgsl"));
-
-            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Post Process Compose Render Pipeline"),
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader_module,
-                    entry_point: "main",
-                    buffers: &[],
+            ReloadablePipeline::new(
+                device,
+                vec![
+                    include_shader!("fullscreen.vertex.wgsl"),
+                    include_shader!("vignette.fragment.wgsl"),
+                ],
+                move |device, shader_modules| {
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        label: None,
+                        layout: Some(&pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &shader_modules[0],
+                            entry_point: "main",
+                            buffers: &[],
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &shader_modules[1],
+                            entry_point: "main",
+                            targets: &[color_target_format.into()],
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                        multiview: None,
+                    })
                 },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader_module,
-                    entry_point: "main",
-                    targets: &[render_target_format.into()],
-                }),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
+            )
         };
 
-        let color_texture_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &sampler,
+            src_texture,
+        );
+
+        Self {
+            uniform_buffer,
+            sampler,
+            bind_group_layout,
+            render_pipeline,
+            bind_group,
+        }
+    }
+
+    pub fn recreate_bind_group(&mut self, device: &wgpu::Device, src_texture: &wgpu::Texture) {
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            &self.sampler,
+            src_texture,
+        );
+    }
 
-        let bloom_texture_view = bloom_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    pub fn update(&self, queue: &wgpu::Queue, vignette: &Vignette) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytes_of(&Uniforms::new(vignette)));
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pipeline.reload(device);
+    }
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    fn create_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        src_texture: &wgpu::Texture,
+    ) -> wgpu::BindGroup {
+        let src_texture_view = src_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: None,
-            layout: &bind_group_layout,
+            layout: bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -133,32 +173,18 @@ impl ComposeRenderPass {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
+                    resource: wgpu::BindingResource::Sampler(sampler),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&color_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::TextureView(&bloom_texture_view),
+                    resource: wgpu::BindingResource::TextureView(&src_texture_view),
                 },
             ],
-        });
-
-        Self {
-            uniform_buffer,
-            render_pipeline,
-            bind_group,
-        }
-    }
-
-    pub fn update(&self, queue: &wgpu::Queue, scene: &Scene) {
-        queue.write_buffer(&self.uniform_buffer, 0, bytes_of(&Uniforms::new(scene)));
+        })
     }
 
     pub fn draw<'rpass>(&'rpass self, rpass: &mut impl wgpu::util::RenderEncoder<'rpass>) {
-        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_pipeline(self.render_pipeline.get());
         rpass.set_bind_group(0, &self.bind_group, &[]);
         rpass.draw(0..3, 0..1);
     }
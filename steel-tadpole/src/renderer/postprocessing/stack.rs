@@ -0,0 +1,275 @@
+use crate::{
+    component::{Effect, PostProcessEffect},
+    entity::{PostProcessing, Scene},
+    renderer::render_graph::{
+        begin_render_pass, Node, NodeTextures, RenderGraph, TextureDescriptor, TextureId,
+    },
+    window::Size,
+};
+
+use super::{
+    bloom_effect::add_bloom, ColorGradingRenderPass, TonemapRenderPass, VignetteRenderPass,
+};
+
+const HDR_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// The passes an enabled effect adds to the render graph, parameters are only applied in the
+// nodes' update
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Layer {
+    Bloom { iterations: u8 },
+    Tonemap,
+    ColorGrading,
+    Vignette,
+}
+
+// The enabled post_processing.effects in order, with their index in it. The render graph is
+// declared again when the stack changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostProcessStack(Vec<(usize, Layer)>);
+
+impl PostProcessStack {
+    pub fn new(post_processing: &PostProcessing) -> Self {
+        let effects = post_processing.effects.iter().enumerate();
+        Self(
+            effects
+                .filter(|(_, effect)| effect.enabled)
+                .map(|(index, PostProcessEffect { effect, .. })| {
+                    let layer = match effect {
+                        Effect::Bloom(bloom) => Layer::Bloom {
+                            iterations: bloom.iterations,
+                        },
+                        Effect::Tonemap(_) => Layer::Tonemap,
+                        Effect::ColorGrading(_) => Layer::ColorGrading,
+                        Effect::Vignette(_) => Layer::Vignette,
+                    };
+                    (index, layer)
+                })
+                .collect(),
+        )
+    }
+
+    // Each effect reads what the previous one wrote. Returns the texture written by the last
+    // effect, or the input if there are none.
+    pub fn add_to_graph(&self, graph: &mut RenderGraph, input: TextureId, size: Size) -> TextureId {
+        self.0.iter().fold(input, |src, &(index, layer)| {
+            add_layer(graph, index, layer, src, size)
+        })
+    }
+}
+
+fn add_layer(
+    graph: &mut RenderGraph,
+    index: usize,
+    layer: Layer,
+    src: TextureId,
+    size: Size,
+) -> TextureId {
+    match layer {
+        Layer::Bloom { iterations } => add_bloom(graph, index, iterations, src, size),
+        Layer::Tonemap => add_effect(graph, format!("Tonemap {}", index), src, {
+            move |device, _, textures| {
+                Box::new(TonemapNode {
+                    index,
+                    render_pass: TonemapRenderPass::new(
+                        device,
+                        textures.reads[0].texture.wgpu_texture(),
+                        textures.writes[0].texture.format(),
+                    ),
+                })
+            }
+        }),
+        Layer::ColorGrading => add_effect(graph, format!("Color Grading {}", index), src, {
+            move |device, queue, textures| {
+                Box::new(ColorGradingNode {
+                    index,
+                    render_pass: ColorGradingRenderPass::new(
+                        device,
+                        queue,
+                        textures.reads[0].texture.wgpu_texture(),
+                        textures.writes[0].texture.format(),
+                    ),
+                })
+            }
+        }),
+        Layer::Vignette => add_effect(graph, format!("Vignette {}", index), src, {
+            move |device, _, textures| {
+                Box::new(VignetteNode {
+                    index,
+                    render_pass: VignetteRenderPass::new(
+                        device,
+                        textures.reads[0].texture.wgpu_texture(),
+                        textures.writes[0].texture.format(),
+                    ),
+                })
+            }
+        }),
+    }
+}
+
+// A single pass reading src and writing a new full size texture
+fn add_effect<F>(graph: &mut RenderGraph, name: String, src: TextureId, create: F) -> TextureId
+where
+    F: FnOnce(&wgpu::Device, &wgpu::Queue, &NodeTextures) -> Box<dyn Node> + 'static,
+{
+    let dst = graph.add_texture(TextureDescriptor {
+        label: format!("{} Texture", name),
+        format: HDR_TEXTURE_FORMAT,
+        divisor: 1,
+    });
+    graph.add_pass(name, &[src], &[dst], create);
+    dst
+}
+
+fn effect(scene: &Scene, index: usize) -> Option<&Effect> {
+    scene
+        .post_processing
+        .effects
+        .get(index)
+        .map(|effect| &effect.effect)
+}
+
+struct TonemapNode {
+    index: usize,
+    render_pass: TonemapRenderPass,
+}
+
+impl Node for TonemapNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.render_pass
+            .recreate_bind_group(device, textures.reads[0].texture.wgpu_texture());
+    }
+
+    fn update(&mut self, _: &wgpu::Device, queue: &wgpu::Queue, scene: &Scene, _: f32) {
+        if let Some(Effect::Tonemap(tonemapper)) = effect(scene, self.index) {
+            self.render_pass
+                .update(queue, *tonemapper, scene.camera.camera.exposure);
+        }
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pass.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.render_pass.draw(&mut begin_render_pass(
+            encoder,
+            "Tonemap Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
+
+struct ColorGradingNode {
+    index: usize,
+    render_pass: ColorGradingRenderPass,
+}
+
+impl Node for ColorGradingNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.render_pass
+            .recreate_bind_group(device, textures.reads[0].texture.wgpu_texture());
+    }
+
+    fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, scene: &Scene, _: f32) {
+        if let Some(Effect::ColorGrading(color_grading)) = effect(scene, self.index) {
+            self.render_pass.update(device, queue, color_grading);
+        }
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pass.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.render_pass.draw(&mut begin_render_pass(
+            encoder,
+            "Color Grading Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
+
+struct VignetteNode {
+    index: usize,
+    render_pass: VignetteRenderPass,
+}
+
+impl Node for VignetteNode {
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures) {
+        self.render_pass
+            .recreate_bind_group(device, textures.reads[0].texture.wgpu_texture());
+    }
+
+    fn update(&mut self, _: &wgpu::Device, queue: &wgpu::Queue, scene: &Scene, _: f32) {
+        if let Some(Effect::Vignette(vignette)) = effect(scene, self.index) {
+            self.render_pass.update(queue, vignette);
+        }
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device) {
+        self.render_pass.reload_shaders(device);
+    }
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures) {
+        self.render_pass.draw(&mut begin_render_pass(
+            encoder,
+            "Vignette Render Pass",
+            &textures.writes[0].texture_view,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{Bloom, Tonemapper, Vignette};
+
+    use super::*;
+
+    fn post_processing(effects: &[(bool, Effect)]) -> PostProcessing {
+        PostProcessing {
+            effects: effects
+                .iter()
+                .map(|(enabled, effect)| PostProcessEffect {
+                    enabled: *enabled,
+                    effect: effect.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn bloom(intensity: f32, iterations: u8) -> Effect {
+        Effect::Bloom(Bloom {
+            intensity,
+            iterations,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn only_enabled_effects_are_stacked() {
+        let stack = PostProcessStack::new(&post_processing(&[
+            (false, bloom(1.0, 4)),
+            (true, Effect::Vignette(Vignette::default())),
+            (true, Effect::Tonemap(Tonemapper::AcesFilmic)),
+        ]));
+        assert_eq!(
+            stack,
+            PostProcessStack(vec![(1, Layer::Vignette), (2, Layer::Tonemap)])
+        );
+    }
+
+    #[test]
+    fn parameters_dont_change_the_stack() {
+        let stack = |intensity, iterations, tonemapper| {
+            PostProcessStack::new(&post_processing(&[
+                (true, bloom(intensity, iterations)),
+                (true, Effect::Tonemap(tonemapper)),
+            ]))
+        };
+        let initial = stack(1.0, 4, Tonemapper::AcesFilmic);
+        assert_eq!(stack(2.0, 4, Tonemapper::AgX), initial);
+        // The bloom passes depend on the iterations
+        assert_ne!(stack(1.0, 5, Tonemapper::AcesFilmic), initial);
+    }
+}
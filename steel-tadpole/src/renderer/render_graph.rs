@@ -0,0 +1,125 @@
+use common::render_graph;
+
+use crate::{entity::Scene, window::Size};
+
+use super::render_target::RenderTarget;
+
+pub use common::render_graph::{TextureDescriptor, TextureId};
+
+// Render targets of a pass in the order it declared them
+pub type NodeTextures<'a> = render_graph::NodeTextures<'a, RenderTarget>;
+
+pub trait Node {
+    // Called when the textures were reallocated, e.g. after a resize
+    fn bind(&mut self, device: &wgpu::Device, textures: &NodeTextures);
+
+    fn update(
+        &mut self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _scene: &Scene,
+        _delta_time: f32,
+    ) {
+    }
+
+    fn reload_shaders(&mut self, device: &wgpu::Device);
+
+    fn run(&self, encoder: &mut wgpu::CommandEncoder, textures: &NodeTextures);
+}
+
+type CreateNode = Box<dyn FnOnce(&wgpu::Device, &wgpu::Queue, &NodeTextures) -> Box<dyn Node>>;
+
+// The shared render graph with render targets for the textures. It's declared again whenever its
+// structure or the output size changes.
+#[derive(Default)]
+pub struct RenderGraph(render_graph::RenderGraph<RenderTarget, Box<dyn Node>, CreateNode>);
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn add_texture(&mut self, descriptor: TextureDescriptor) -> TextureId {
+        self.0.add_texture(descriptor)
+    }
+
+    pub fn retain(&mut self, texture: TextureId) {
+        self.0.retain(texture);
+    }
+
+    pub fn add_pass<F>(
+        &mut self,
+        name: impl Into<String>,
+        reads: &[TextureId],
+        writes: &[TextureId],
+        create: F,
+    ) where
+        F: FnOnce(&wgpu::Device, &wgpu::Queue, &NodeTextures) -> Box<dyn Node> + 'static,
+    {
+        self.0.add_pass(name, reads, writes, Box::new(create));
+    }
+
+    pub fn compile(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, size: Size) {
+        self.0.compile(
+            size.width,
+            size.height,
+            |descriptor, width, height| {
+                RenderTarget::new(device, &descriptor.label, width, height, descriptor.format)
+            },
+            |node, textures| node.bind(device, textures),
+            |create, textures| create(device, queue, textures),
+        );
+    }
+
+    pub fn render_target(&self, texture: TextureId) -> &RenderTarget {
+        self.0.target(texture)
+    }
+
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scene: &Scene,
+        delta_time: f32,
+    ) {
+        for node in self.0.nodes_mut() {
+            node.update(device, queue, scene, delta_time);
+        }
+    }
+
+    pub fn reload_shaders(&mut self, device: &wgpu::Device) {
+        for node in self.0.nodes_mut() {
+            node.reload_shaders(device);
+        }
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder) {
+        for (node, textures) in self.0.scheduled() {
+            node.run(encoder, &textures);
+        }
+    }
+}
+
+// Render pass clearing and then drawing to a single color attachment
+pub fn begin_render_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    label: &'a str,
+    view: &'a wgpu::TextureView,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: true,
+            },
+        }],
+        depth_stencil_attachment: None,
+    })
+}
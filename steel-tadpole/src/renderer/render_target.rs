@@ -0,0 +1,42 @@
+use super::wgpu_ext::{self, DeviceExt};
+
+pub struct RenderTarget {
+    pub texture: wgpu_ext::Texture,
+    pub texture_view: wgpu::TextureView,
+}
+
+impl RenderTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> RenderTarget {
+        let texture = device.create_texture_ext(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+        });
+
+        let texture_view = texture
+            .wgpu_texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            texture_view,
+        }
+    }
+}
@@ -1,13 +1,12 @@
+use std::num::NonZeroU32;
+
+use anyhow::Result;
+
 pub struct Texture {
     wgpu_texture: wgpu::Texture,
     width: u32,
     height: u32,
-    depth_or_array_layers: u32,
-    mip_level_count: u32,
-    sample_count: u32,
-    dimension: wgpu::TextureDimension,
     format: wgpu::TextureFormat,
-    usage: wgpu::TextureUsages,
 }
 
 impl Texture {
@@ -26,35 +25,10 @@ impl Texture {
         self.height
     }
 
-    #[inline]
-    pub fn depth_or_array_layers(&self) -> u32 {
-        self.depth_or_array_layers
-    }
-
-    #[inline]
-    pub fn mip_level_count(&self) -> u32 {
-        self.mip_level_count
-    }
-
-    #[inline]
-    pub fn sample_count(&self) -> u32 {
-        self.sample_count
-    }
-
-    #[inline]
-    pub fn dimension(&self) -> wgpu::TextureDimension {
-        self.dimension
-    }
-
     #[inline]
     pub fn format(&self) -> wgpu::TextureFormat {
         self.format
     }
-
-    #[inline]
-    pub fn usage(&self) -> wgpu::TextureUsages {
-        self.usage
-    }
 }
 
 impl AsRef<wgpu::Texture> for Texture {
@@ -73,12 +47,68 @@ impl DeviceExt for wgpu::Device {
             wgpu_texture: self.create_texture(desc),
             width: desc.size.width,
             height: desc.size.height,
-            depth_or_array_layers: desc.size.depth_or_array_layers,
-            mip_level_count: desc.mip_level_count,
-            sample_count: desc.sample_count,
-            dimension: desc.dimension,
             format: desc.format,
-            usage: desc.usage,
         }
     }
 }
+
+pub async fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &Texture,
+) -> Result<Vec<u8>> {
+    let unpadded_bytes_per_row = texture.width() * texture.format().describe().block_size as u32;
+    let padded_bytes_per_row = {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        unpadded_bytes_per_row.div_ceil(align) * align
+    };
+
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Texture Readback Buffer"),
+        size: (padded_bytes_per_row * texture.height()) as _,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture: texture.wgpu_texture(),
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &readback_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        wgpu::Extent3d {
+            width: texture.width(),
+            height: texture.height(),
+            depth_or_array_layers: 1,
+        },
+    );
+
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let buffer_slice = readback_buffer.slice(..);
+    let mapping = buffer_slice.map_async(wgpu::MapMode::Read);
+    device.poll(wgpu::Maintain::Wait);
+    mapping.await?;
+
+    let data = buffer_slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as _)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as _])
+        .copied()
+        .collect::<Vec<_>>();
+
+    readback_buffer.unmap();
+
+    Ok(data)
+}
@@ -0,0 +1,357 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use glam::Vec3;
+
+use crate::{
+    animation::Animation,
+    component::{ColorGrading, Effect, Tonemapper},
+    entity::{Entity, Scene},
+};
+
+const DEFAULT_SCENE: &str = include_str!("../scenes/default.ron");
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn load(path: &Path) -> Result<Scene> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read scene file: {}", path.display()))?;
+
+    let mut scene: Scene = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("ron") => ron::from_str(&source).map_err(anyhow::Error::from),
+        Some("json") => serde_json::from_str(&source).map_err(anyhow::Error::from),
+        _ => bail!(
+            "Unsupported scene file extension, expected .ron or .json: {}",
+            path.display()
+        ),
+    }
+    .with_context(|| format!("Failed to parse scene file: {}", path.display()))?;
+
+    validate(&scene).with_context(|| format!("Invalid scene file: {}", path.display()))?;
+
+    for effect in &mut scene.post_processing.effects {
+        if let Effect::ColorGrading(ColorGrading { lut: Some(lut), .. }) = &mut effect.effect {
+            if let Some(parent) = path.parent() {
+                *lut = parent.join(&*lut);
+            }
+        }
+    }
+
+    Ok(scene)
+}
+
+pub fn default_scene() -> Scene {
+    ron::from_str(DEFAULT_SCENE).expect("Failed to parse default scene")
+}
+
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checked_at: Instant,
+}
+
+impl Watcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified,
+            checked_at: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn poll(&mut self) -> Option<Result<Scene>> {
+        if self.checked_at.elapsed() < WATCH_INTERVAL {
+            return None;
+        }
+        self.checked_at = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(load(&self.path))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn validate(scene: &Scene) -> Result<()> {
+    let camera = &scene.camera.camera;
+    ensure!(
+        camera.fov > 0.0 && camera.fov < 180.0,
+        "camera.camera.fov must be between 0 and 180 degrees"
+    );
+    ensure!(
+        camera.near > 0.0 && (camera.infinite_far || camera.near < camera.far),
+        "camera.camera.near must be greater than 0 and less than far"
+    );
+    if let Some(auto_exposure) = &camera.auto_exposure {
+        auto_exposure
+            .validate()
+            .context("Invalid camera.camera.auto_exposure")?;
+    }
+    if let Some(path) = &scene.camera.path {
+        path.validate().context("Invalid camera.path")?;
+    }
+
+    // Without a loop, following parents reaches a root within as many steps as there are entities
+    for entity in Entity::ALL {
+        let mut ancestor = scene.parent(entity);
+        for _ in Entity::ALL {
+            if let Some(parent) = ancestor {
+                ensure!(
+                    parent != entity,
+                    "{:?} must not be parented to itself or its descendants",
+                    entity
+                );
+                ancestor = scene.parent(parent);
+            }
+        }
+    }
+
+    let particle = &scene.particle.particle;
+    ensure!(
+        particle.max_count > 0,
+        "particle.particle.max_count must be greater than 0"
+    );
+    ensure!(
+        particle.speed_range.0 <= particle.speed_range.1,
+        "particle.particle.speed_range must be (min, max)"
+    );
+    ensure!(
+        particle.color_range.0.cmple(particle.color_range.1).all(),
+        "particle.particle.color_range must be (min, max)"
+    );
+    ensure!(
+        particle
+            .position_range
+            .0
+            .cmple(particle.position_range.1)
+            .all(),
+        "particle.particle.position_range must be (min, max)"
+    );
+
+    // Exposure is applied by the tonemap pass, so without one it would be ignored, and with more
+    // it would be applied more than once
+    let enabled_effects = || {
+        scene
+            .post_processing
+            .effects
+            .iter()
+            .filter(|effect| effect.enabled)
+            .map(|effect| &effect.effect)
+    };
+    ensure!(
+        enabled_effects()
+            .filter(|effect| matches!(effect, Effect::Tonemap(_)))
+            .count()
+            == 1,
+        "post_processing.effects must enable exactly one Tonemap, use Tonemap(None) to only apply \
+         the exposure"
+    );
+    // Grading clamps to the display range that the LUTs expect, which would clip the HDR colors
+    ensure!(
+        enabled_effects()
+            .take_while(|effect| !matches!(effect, Effect::Tonemap(_)))
+            .all(|effect| !matches!(effect, Effect::ColorGrading(_))),
+        "post_processing.effects must enable ColorGrading after the Tonemap"
+    );
+
+    for (i, effect) in scene.post_processing.effects.iter().enumerate() {
+        validate_effect(&effect.effect)
+            .with_context(|| format!("Invalid post_processing.effects[{}]", i))?;
+    }
+
+    for (i, animation) in scene.animations.iter().enumerate() {
+        validate_animation(animation, scene)
+            .with_context(|| format!("Invalid animations[{}]", i))?;
+    }
+
+    Ok(())
+}
+
+fn validate_animation(animation: &Animation, scene: &Scene) -> Result<()> {
+    match animation {
+        Animation::ParticlePosition(curve)
+        | Animation::ParticleRotation(curve)
+        | Animation::ParticleScale(curve) => curve.validate(),
+        Animation::ParticleSize(curve) | Animation::BloomThreshold(curve) => {
+            curve.validate()?;
+            ensure!(
+                curve.keyframes.iter().all(|keyframe| keyframe.value >= 0.0),
+                "Values must not be negative"
+            );
+            Ok(())
+        }
+        Animation::EmissionRate(curve) => {
+            curve.validate()?;
+            ensure!(
+                curve.keyframes.iter().all(|keyframe| keyframe.value > 0.0),
+                "EmissionRate values must be greater than 0"
+            );
+            Ok(())
+        }
+        Animation::Exposure(curve) => {
+            curve.validate()?;
+            ensure!(
+                curve.keyframes.iter().all(|keyframe| keyframe.value > 0.0),
+                "Exposure values must be greater than 0"
+            );
+            ensure!(
+                scene.camera.camera.auto_exposure.is_none(),
+                "Exposure can't be animated while camera.camera.auto_exposure is set"
+            );
+            Ok(())
+        }
+    }
+}
+
+fn validate_effect(effect: &Effect) -> Result<()> {
+    match effect {
+        Effect::Bloom(bloom) => {
+            ensure!(
+                bloom.iterations > 0,
+                "Bloom iterations must be greater than 0"
+            );
+            ensure!(bloom.knee >= 0.0, "Bloom knee must not be negative");
+            ensure!(
+                (0.0..=1.0).contains(&bloom.scatter),
+                "Bloom scatter must be between 0 and 1"
+            );
+        }
+        Effect::Tonemap(tonemapper) => {
+            if let Tonemapper::ExtendedReinhard { white_point } = tonemapper {
+                ensure!(
+                    *white_point > 0.0,
+                    "Tonemap white_point must be greater than 0"
+                );
+            }
+        }
+        Effect::ColorGrading(color_grading) => {
+            ensure!(
+                (-1.0..=1.0).contains(&color_grading.temperature)
+                    && (-1.0..=1.0).contains(&color_grading.tint),
+                "ColorGrading temperature and tint must be between -1 and 1"
+            );
+            ensure!(
+                color_grading.contrast > 0.0,
+                "ColorGrading contrast must be greater than 0"
+            );
+            ensure!(
+                color_grading.gamma.cmpgt(Vec3::ZERO).all(),
+                "ColorGrading gamma must be greater than 0"
+            );
+            ensure!(
+                color_grading.saturation >= 0.0,
+                "ColorGrading saturation must not be negative"
+            );
+        }
+        Effect::Vignette(vignette) => {
+            ensure!(
+                (0.0..=1.0).contains(&vignette.intensity),
+                "Vignette intensity must be between 0 and 1"
+            );
+            ensure!(
+                vignette.smoothness > 0.0 && vignette.smoothness <= 1.0,
+                "Vignette smoothness must be greater than 0 and at most 1"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_scenes_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(err) = load(&path) {
+                panic!("{:?}", err);
+            }
+        }
+    }
+
+    #[test]
+    fn parent_loops_are_invalid() {
+        let mut scene = default_scene();
+        scene.camera.parent = Some(Entity::Particle);
+        assert!(validate(&scene).is_ok());
+
+        scene.particle.parent = Some(Entity::Camera);
+        assert!(validate(&scene).is_err());
+
+        scene.camera.parent = None;
+        scene.particle.parent = Some(Entity::Particle);
+        assert!(validate(&scene).is_err());
+    }
+
+    #[test]
+    fn exactly_one_tonemap_must_be_enabled() {
+        let mut scene = default_scene();
+        let tonemap = scene
+            .post_processing
+            .effects
+            .iter()
+            .position(|effect| matches!(effect.effect, Effect::Tonemap(_)))
+            .unwrap();
+        assert!(validate(&scene).is_ok());
+
+        scene.post_processing.effects[tonemap].enabled = false;
+        assert!(validate(&scene).is_err());
+
+        scene.post_processing.effects[tonemap].enabled = true;
+        let duplicate = scene.post_processing.effects[tonemap].clone();
+        scene.post_processing.effects.push(duplicate);
+        assert!(validate(&scene).is_err());
+    }
+
+    #[test]
+    fn color_grading_must_come_after_tonemap() {
+        let mut scene = default_scene();
+        let effects = &scene.post_processing.effects;
+        let position = |is_effect: fn(&Effect) -> bool| {
+            effects
+                .iter()
+                .position(|effect| is_effect(&effect.effect))
+                .unwrap()
+        };
+        let tonemap = position(|effect| matches!(effect, Effect::Tonemap(_)));
+        let color_grading = position(|effect| matches!(effect, Effect::ColorGrading(_)));
+        assert!(validate(&scene).is_ok());
+
+        scene.post_processing.effects.swap(tonemap, color_grading);
+        assert!(validate(&scene).is_err());
+
+        // The grading is now where the tonemap was, disabled it doesn't clip anything
+        scene.post_processing.effects[tonemap].enabled = false;
+        assert!(validate(&scene).is_ok());
+    }
+
+    #[test]
+    fn animations_default_to_none() {
+        let mut value = serde_json::to_value(default_scene()).unwrap();
+        value.as_object_mut().unwrap().remove("animations");
+
+        let scene: Scene = serde_json::from_value(value).unwrap();
+        assert!(scene.animations.is_empty());
+    }
+}